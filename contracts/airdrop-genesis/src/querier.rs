@@ -79,9 +79,7 @@ pub fn check_if_user_participated_in_ido(
             msg: to_binary(&FunderInfo {
                 address: deps.api.addr_humanize(&account_addr)?.into_string(),
             })?,
-        })).unwrap_or(ParticipantResponse {
-        is_joined: false,
-    });
+        })).unwrap_or_default();
 
     return Ok(res.is_joined);
 }
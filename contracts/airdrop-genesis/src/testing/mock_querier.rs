@@ -162,6 +162,7 @@ impl WasmMockQuerier {
 
                         SystemResult::Ok(ContractResult::from(to_binary(&ParticipantResponse {
                             is_joined: value.is_joined,
+                            ..Default::default()
                         })))
                     }
                 }
@@ -494,7 +494,8 @@ fn double_claim_after_staking_should_not_reject() {
                 vec![(
                     String::from("terra1qfqa2eu9wp272ha93lj4yhcenrc6ymng079nu8"),
                     ParticipantResponse {
-                        is_joined: true
+                        is_joined: true,
+                        ..Default::default()
                     }
                 )],
             ),
@@ -503,7 +504,8 @@ fn double_claim_after_staking_should_not_reject() {
                 vec![(
                     String::from("terra1csnmlw0v0pyy36tk7scfwvh8ujpnydu5dtfj58"),
                     ParticipantResponse {
-                        is_joined: true
+                        is_joined: true,
+                        ..Default::default()
                     }
                 )],
             )
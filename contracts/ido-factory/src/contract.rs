@@ -0,0 +1,90 @@
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult, to_binary};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use starterra_token::ido_factory::{InstantiateMsg, MigrateMsg, QueryMsg, ExecuteMsg};
+
+use crate::state::{Config, store_config};
+use crate::errors::ContractError;
+use crate::execute::{update_config, accept_ownership, store_ido_template, remove_ido_template, create_ido, register_ido, sync_ido_status};
+use crate::queries::{query_config, query_template, query_ido, query_idos, query_is_participant_in_any_ido};
+
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    let config = Config {
+        owner: deps.api.addr_canonicalize(&msg.owner)?,
+        ido_code_id: msg.ido_code_id,
+        number_of_idos: 0,
+    };
+
+    store_config(
+        deps.storage,
+        &config,
+    )?;
+
+    Ok(Response::new())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateConfig { owner, ido_code_id } => {
+            update_config(deps, info, owner, ido_code_id)
+        }
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::StoreTemplate { name, template } => {
+            store_ido_template(deps, info, name, template)
+        }
+        ExecuteMsg::RemoveTemplate { name } => remove_ido_template(deps, info, name),
+        ExecuteMsg::CreateIdo(create_msg) => create_ido(deps, env, info, create_msg),
+        ExecuteMsg::SyncIdoStatus { ido_id } => sync_ido_status(deps, ido_id),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    deps: DepsMut,
+    _env: Env,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    register_ido(deps, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(
+    deps: Deps,
+    _env: Env,
+    msg: QueryMsg,
+) -> Result<Binary, ContractError> {
+    match msg {
+        QueryMsg::Config {} => Ok(to_binary(&query_config(deps)?)?),
+        QueryMsg::Template { name } => Ok(to_binary(&query_template(deps, name)?)?),
+        QueryMsg::Ido { ido_id } => Ok(to_binary(&query_ido(deps, ido_id)?)?),
+        QueryMsg::Idos { status, start_after, limit, order_by } => {
+            Ok(to_binary(&query_idos(deps, status, start_after, limit, order_by)?)?)
+        }
+        QueryMsg::IsParticipantInAnyIdo { address, start_after, limit } => {
+            Ok(to_binary(&query_is_participant_in_any_ido(deps, address, start_after, limit)?)?)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> StdResult<Response> {
+    Ok(Response::default())
+}
@@ -0,0 +1,23 @@
+use cosmwasm_std::{StdError};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Pending owner missing")]
+    PendingOwnerMissing {},
+
+    #[error("Template not found")]
+    TemplateNotFound {},
+
+    #[error("Unknown reply id")]
+    UnknownReplyId {},
+
+    #[error("Instantiated contract address missing")]
+    ContractAddressMissing {},
+}
@@ -0,0 +1,187 @@
+use cosmwasm_std::{to_binary, DepsMut, Env, MessageInfo, Reply, Response, SubMsg, WasmMsg, ContractResult};
+use starterra_token::ido::InstantiateMsg as IdoInstantiateMsg;
+use starterra_token::ido_factory::{CreateIdoMsg, IdoStatus};
+
+use crate::state::{Config, store_config, read_config, store_pending_owner, remove_pending_owner, read_pending_owner, store_template, remove_template, read_template, PendingIdo, store_pending_ido, read_pending_ido, remove_pending_ido, IdoInfo, store_ido, read_ido};
+use crate::errors::ContractError;
+use crate::tools::{assert_owner_privilege, fetch_ido_status};
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: Option<String>,
+    ido_code_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(owner) = owner {
+        store_pending_owner(deps.storage, &deps.api.addr_canonicalize(&owner)?)?;
+    }
+
+    if let Some(ido_code_id) = ido_code_id {
+        config.ido_code_id = ido_code_id;
+    }
+
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+pub fn accept_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    match read_pending_owner(deps.storage) {
+        None => {
+            return Err(ContractError::PendingOwnerMissing {});
+        }
+        Some(pending_owner) => {
+            let mut config: Config = read_config(deps.storage)?;
+            if deps.api.addr_canonicalize(info.sender.as_str())? != pending_owner {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            config.owner = pending_owner;
+            store_config(deps.storage, &config)?;
+            remove_pending_owner(deps.storage);
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", info.sender))
+}
+
+pub fn store_ido_template(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    template: IdoInstantiateMsg,
+) -> Result<Response, ContractError> {
+    assert_owner_privilege(deps.as_ref(), &info)?;
+    store_template(deps.storage, &name, &template)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "store_template")
+        .add_attribute("name", name))
+}
+
+pub fn remove_ido_template(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    assert_owner_privilege(deps.as_ref(), &info)?;
+    if read_template(deps.storage, &name)?.is_none() {
+        return Err(ContractError::TemplateNotFound {});
+    }
+    remove_template(deps.storage, &name);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_template")
+        .add_attribute("name", name))
+}
+
+pub fn create_ido(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    create_msg: CreateIdoMsg,
+) -> Result<Response, ContractError> {
+    assert_owner_privilege(deps.as_ref(), &info)?;
+    let config: Config = read_config(deps.storage)?;
+    let CreateIdoMsg { template, label, ido_token, ido_token_price, end_date, phases } = create_msg;
+
+    let mut msg = match read_template(deps.storage, &template)? {
+        Some(msg) => msg,
+        None => return Err(ContractError::TemplateNotFound {}),
+    };
+    msg.ido_token = ido_token;
+    msg.ido_token_price = ido_token_price;
+    msg.end_date = end_date;
+    if let Some(phases) = phases {
+        msg.phases = phases;
+    }
+
+    let ido_id = config.number_of_idos + 1;
+    store_pending_ido(deps.storage, &PendingIdo {
+        ido_id,
+        label: label.clone(),
+        template: template.clone(),
+        created_at: env.block.time.seconds(),
+    })?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Instantiate {
+                admin: Some(deps.api.addr_humanize(&config.owner)?.into_string()),
+                code_id: config.ido_code_id,
+                msg: to_binary(&msg)?,
+                funds: vec![],
+                label: label.clone(),
+            },
+            ido_id,
+        ))
+        .add_attribute("action", "create_ido")
+        .add_attribute("ido_id", ido_id.to_string())
+        .add_attribute("template", template)
+        .add_attribute("label", label))
+}
+
+pub fn register_ido(
+    deps: DepsMut,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    let pending_ido = match read_pending_ido(deps.storage)? {
+        Some(pending_ido) if pending_ido.ido_id == msg.id => pending_ido,
+        _ => return Err(ContractError::UnknownReplyId {}),
+    };
+
+    let events = match msg.result {
+        ContractResult::Ok(response) => response.events,
+        ContractResult::Err(_) => return Err(ContractError::UnknownReplyId {}),
+    };
+    let contract_address = events.iter()
+        .filter(|event| event.ty == "instantiate_contract")
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == "contract_address")
+        .map(|attr| attr.value.clone())
+        .ok_or(ContractError::ContractAddressMissing {})?;
+
+    let mut ido = IdoInfo {
+        address: deps.api.addr_canonicalize(&contract_address)?,
+        label: pending_ido.label,
+        template: pending_ido.template,
+        created_at: pending_ido.created_at,
+        status: IdoStatus::Upcoming,
+    };
+    // a sale without phases is live from instantiation, so it is indexed with its real status
+    ido.status = fetch_ido_status(deps.as_ref(), &ido)?;
+    store_ido(deps.storage, pending_ido.ido_id, &ido)?;
+    remove_pending_ido(deps.storage);
+
+    let mut config: Config = read_config(deps.storage)?;
+    config.number_of_idos = pending_ido.ido_id;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_ido")
+        .add_attribute("ido_id", pending_ido.ido_id.to_string())
+        .add_attribute("ido_address", contract_address))
+}
+
+pub fn sync_ido_status(
+    deps: DepsMut,
+    ido_id: u64,
+) -> Result<Response, ContractError> {
+    let mut ido = read_ido(deps.storage, ido_id)?;
+    ido.status = fetch_ido_status(deps.as_ref(), &ido)?;
+    store_ido(deps.storage, ido_id, &ido)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sync_ido_status")
+        .add_attribute("ido_id", ido_id.to_string()))
+}
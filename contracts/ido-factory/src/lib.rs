@@ -0,0 +1,10 @@
+pub mod contract;
+pub mod state;
+pub mod querier;
+pub mod errors;
+pub mod execute;
+pub mod queries;
+pub mod tools;
+
+#[cfg(test)]
+mod testing;
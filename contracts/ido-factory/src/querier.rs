@@ -0,0 +1,29 @@
+use cosmwasm_std::{to_binary, CanonicalAddr, QueryRequest, StdResult, WasmQuery, Deps};
+
+use starterra_token::ido::QueryMsg::{Status, FunderInfo};
+use starterra_token::ido::{StatusResponse, ParticipantResponse};
+
+pub fn load_ido_status(
+    deps: Deps,
+    contract_addr: &CanonicalAddr,
+) -> StdResult<StatusResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: deps.api.addr_humanize(contract_addr)?.into_string(),
+        msg: to_binary(&Status {
+            block_time: None,
+        })?,
+    }))
+}
+
+pub fn load_ido_participant(
+    deps: Deps,
+    contract_addr: &CanonicalAddr,
+    address: &str,
+) -> StdResult<ParticipantResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: deps.api.addr_humanize(contract_addr)?.into_string(),
+        msg: to_binary(&FunderInfo {
+            address: address.to_string(),
+        })?,
+    }))
+}
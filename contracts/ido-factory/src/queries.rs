@@ -0,0 +1,94 @@
+use cosmwasm_std::Deps;
+use starterra_token::common::OrderBy;
+use starterra_token::ido_factory::{ConfigResponse, TemplateResponse, IdoResponse, IdosResponse, IdoStatus, IsParticipantResponse};
+
+use crate::state::{read_config, read_template, read_ido, read_idos, read_idos_by_status, IdoInfo};
+use crate::errors::ContractError;
+use crate::querier::load_ido_participant;
+
+pub fn query_config(
+    deps: Deps,
+) -> Result<ConfigResponse, ContractError> {
+    let state = read_config(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: deps.api.addr_humanize(&state.owner)?.into_string(),
+        ido_code_id: state.ido_code_id,
+        number_of_idos: state.number_of_idos,
+    })
+}
+
+pub fn query_template(
+    deps: Deps,
+    name: String,
+) -> Result<TemplateResponse, ContractError> {
+    match read_template(deps.storage, &name)? {
+        Some(template) => Ok(TemplateResponse { name, template }),
+        None => Err(ContractError::TemplateNotFound {}),
+    }
+}
+
+pub fn query_ido(
+    deps: Deps,
+    ido_id: u64,
+) -> Result<IdoResponse, ContractError> {
+    let ido = read_ido(deps.storage, ido_id)?;
+    to_ido_response(deps, ido_id, ido)
+}
+
+pub fn query_idos(
+    deps: Deps,
+    status: Option<IdoStatus>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> Result<IdosResponse, ContractError> {
+    let idos = match status {
+        Some(status) => read_idos_by_status(deps.storage, &status, start_after, limit, order_by)?,
+        None => read_idos(deps.storage, start_after, limit, order_by)?,
+    };
+
+    let idos = idos.into_iter()
+        .map(|(ido_id, ido)| to_ido_response(deps, ido_id, ido))
+        .collect::<Result<Vec<IdoResponse>, ContractError>>()?;
+
+    Ok(IdosResponse { idos })
+}
+
+pub fn query_is_participant_in_any_ido(
+    deps: Deps,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<IsParticipantResponse, ContractError> {
+    deps.api.addr_validate(&address)?;
+    let mut is_participant = false;
+    let mut last_ido_id = None;
+    for (ido_id, ido) in read_idos(deps.storage, start_after, limit, Some(OrderBy::Asc))? {
+        last_ido_id = Some(ido_id);
+        if load_ido_participant(deps, &ido.address, &address)?.is_joined {
+            is_participant = true;
+            break;
+        }
+    }
+
+    Ok(IsParticipantResponse {
+        address,
+        is_participant,
+        last_ido_id,
+    })
+}
+
+fn to_ido_response(
+    deps: Deps,
+    ido_id: u64,
+    ido: IdoInfo,
+) -> Result<IdoResponse, ContractError> {
+    Ok(IdoResponse {
+        ido_id,
+        address: deps.api.addr_humanize(&ido.address)?.into_string(),
+        label: ido.label,
+        template: ido.template,
+        created_at: ido.created_at,
+        status: ido.status,
+    })
+}
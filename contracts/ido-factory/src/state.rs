@@ -0,0 +1,179 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read, Singleton, Bucket, ReadonlyBucket};
+use starterra_token::common::OrderBy;
+use starterra_token::ido::InstantiateMsg as IdoInstantiateMsg;
+use starterra_token::ido_factory::IdoStatus;
+
+static KEY_CONFIG: &[u8] = b"config";
+static KEY_PENDING_OWNER: &[u8] = b"pending_owner";
+static KEY_PENDING_IDO: &[u8] = b"pending_ido";
+
+static PREFIX_KEY_TEMPLATE: &[u8] = b"template";
+static PREFIX_KEY_IDO: &[u8] = b"ido";
+static PREFIX_KEY_IDO_STATUS: &[u8] = b"ido_status";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner: CanonicalAddr,
+    pub ido_code_id: u64,
+    pub number_of_idos: u64,
+}
+
+pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
+    singleton(storage, KEY_CONFIG).save(config)
+}
+
+pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+pub fn store_pending_owner(storage: &mut dyn Storage, new_owner: &CanonicalAddr) -> StdResult<()> {
+    singleton(storage, KEY_PENDING_OWNER).save(new_owner)
+}
+
+pub fn read_pending_owner(storage: &dyn Storage) -> Option<CanonicalAddr> {
+    singleton_read(storage, KEY_PENDING_OWNER).may_load().unwrap()
+}
+
+pub fn remove_pending_owner(storage: &mut dyn Storage) {
+    Singleton::<CanonicalAddr>::new(storage, KEY_PENDING_OWNER).remove();
+}
+
+// Kept between CreateIdo and its instantiate reply, the address is only known in the reply
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingIdo {
+    pub ido_id: u64,
+    pub label: String,
+    pub template: String,
+    pub created_at: u64,
+}
+
+pub fn store_pending_ido(storage: &mut dyn Storage, pending_ido: &PendingIdo) -> StdResult<()> {
+    singleton(storage, KEY_PENDING_IDO).save(pending_ido)
+}
+
+pub fn read_pending_ido(storage: &dyn Storage) -> StdResult<Option<PendingIdo>> {
+    singleton_read(storage, KEY_PENDING_IDO).may_load()
+}
+
+pub fn remove_pending_ido(storage: &mut dyn Storage) {
+    Singleton::<PendingIdo>::new(storage, KEY_PENDING_IDO).remove();
+}
+
+pub fn store_template(
+    storage: &mut dyn Storage,
+    name: &str,
+    template: &IdoInstantiateMsg,
+) -> StdResult<()> {
+    Bucket::<IdoInstantiateMsg>::new(storage, PREFIX_KEY_TEMPLATE).save(name.as_bytes(), template)
+}
+
+pub fn remove_template(storage: &mut dyn Storage, name: &str) {
+    Bucket::<IdoInstantiateMsg>::new(storage, PREFIX_KEY_TEMPLATE).remove(name.as_bytes())
+}
+
+pub fn read_template(
+    storage: &dyn Storage,
+    name: &str,
+) -> StdResult<Option<IdoInstantiateMsg>> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_TEMPLATE).may_load(name.as_bytes())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IdoInfo {
+    pub address: CanonicalAddr,
+    pub label: String,
+    pub template: String,
+    pub created_at: u64,
+    pub status: IdoStatus,
+}
+
+// Also moves the sale to its status index, so the previous status is taken from the stored info
+pub fn store_ido(storage: &mut dyn Storage, ido_id: u64, ido: &IdoInfo) -> StdResult<()> {
+    let key = ido_id.to_be_bytes();
+    if let Some(previous) = ReadonlyBucket::<IdoInfo>::new(storage, PREFIX_KEY_IDO).may_load(&key)? {
+        Bucket::<u64>::multilevel(storage, &[PREFIX_KEY_IDO_STATUS, status_key(&previous.status)]).remove(&key);
+    }
+    Bucket::<u64>::multilevel(storage, &[PREFIX_KEY_IDO_STATUS, status_key(&ido.status)]).save(&key, &ido_id)?;
+    Bucket::<IdoInfo>::new(storage, PREFIX_KEY_IDO).save(&key, ido)
+}
+
+pub fn read_ido(storage: &dyn Storage, ido_id: u64) -> StdResult<IdoInfo> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_IDO).load(&ido_id.to_be_bytes())
+}
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+pub fn read_idos(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<Vec<(u64, IdoInfo)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let (start, end, order_by) = match order_by {
+        Some(OrderBy::Asc) => (calc_range_start_id(start_after), None, OrderBy::Asc),
+        _ => (None, calc_range_end_id(start_after), OrderBy::Desc),
+    };
+
+    let idos: ReadonlyBucket<IdoInfo> = ReadonlyBucket::new(storage, PREFIX_KEY_IDO);
+
+    idos.range(start.as_deref(), end.as_deref(), order_by.into())
+        .take(limit)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok((id_from_key(&k), v))
+        })
+        .collect()
+}
+
+// reads the status index, so only sales with the requested status are visited
+pub fn read_idos_by_status(
+    storage: &dyn Storage,
+    status: &IdoStatus,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<Vec<(u64, IdoInfo)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let (start, end, order_by) = match order_by {
+        Some(OrderBy::Asc) => (calc_range_start_id(start_after), None, OrderBy::Asc),
+        _ => (None, calc_range_end_id(start_after), OrderBy::Desc),
+    };
+
+    let ido_ids: ReadonlyBucket<u64> = ReadonlyBucket::multilevel(storage, &[PREFIX_KEY_IDO_STATUS, status_key(status)]);
+
+    ido_ids.range(start.as_deref(), end.as_deref(), order_by.into())
+        .take(limit)
+        .map(|item| {
+            let (_, ido_id) = item?;
+            Ok((ido_id, read_ido(storage, ido_id)?))
+        })
+        .collect()
+}
+
+fn status_key(status: &IdoStatus) -> &'static [u8] {
+    match status {
+        IdoStatus::Upcoming => b"upcoming",
+        IdoStatus::Live => b"live",
+        IdoStatus::Closed => b"closed",
+    }
+}
+
+fn id_from_key(key: &[u8]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(key);
+    u64::from_be_bytes(bytes)
+}
+
+// ids are stored big endian, so the next id is the first key after the provided one
+fn calc_range_start_id(start_after: Option<u64>) -> Option<Vec<u8>> {
+    start_after.map(|id| id.saturating_add(1).to_be_bytes().to_vec())
+}
+
+fn calc_range_end_id(start_after: Option<u64>) -> Option<Vec<u8>> {
+    start_after.map(|id| id.to_be_bytes().to_vec())
+}
@@ -0,0 +1,96 @@
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{from_slice, to_binary, Coin, Empty, Querier, QuerierResult, QueryRequest, SystemError, WasmQuery, OwnedDeps, SystemResult, ContractResult, from_binary};
+use std::collections::HashMap;
+
+use starterra_token::ido::{ParticipantResponse, StatusResponse};
+
+/// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
+/// this uses our CustomQuerier.
+pub fn mock_dependencies(
+    contract_balance: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let custom_querier: WasmMockQuerier =
+        WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
+
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: custom_querier,
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier<Empty>,
+    ido_querier: IdoQuerier,
+}
+
+#[derive(Clone, Default)]
+pub struct IdoQuerier {
+    // ido contract address -> status
+    status: HashMap<String, StatusResponse>,
+    // ido contract address -> joined participants
+    participants: HashMap<String, Vec<String>>,
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        // MockQuerier doesn't support Custom, so we ignore it completely here
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                });
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
+        match &request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                match from_binary(msg).unwrap() {
+                    starterra_token::ido::QueryMsg::Status { block_time: _ } => {
+                        match self.ido_querier.status.get(contract_addr) {
+                            Some(status) => SystemResult::Ok(ContractResult::from(to_binary(status))),
+                            None => SystemResult::Err(SystemError::NoSuchContract {
+                                addr: contract_addr.clone(),
+                            }),
+                        }
+                    }
+                    starterra_token::ido::QueryMsg::FunderInfo { address } => {
+                        let is_joined = self.ido_querier.participants
+                            .get(contract_addr)
+                            .map_or(false, |participants| participants.contains(&address));
+                        SystemResult::Ok(ContractResult::from(to_binary(&ParticipantResponse {
+                            is_joined,
+                            ..ParticipantResponse::default()
+                        })))
+                    }
+                    _ => self.base.handle_query(request),
+                }
+            }
+            _ => self.base.handle_query(request),
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(base: MockQuerier<Empty>) -> Self {
+        WasmMockQuerier {
+            base,
+            ido_querier: IdoQuerier::default(),
+        }
+    }
+
+    pub fn with_ido_status(&mut self, status: Vec<(String, StatusResponse)>) {
+        self.ido_querier.status = status.into_iter().collect();
+    }
+
+    pub fn with_ido_participants(&mut self, participants: Vec<(String, Vec<String>)>) {
+        self.ido_querier.participants = participants.into_iter().collect();
+    }
+}
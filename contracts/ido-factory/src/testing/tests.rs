@@ -0,0 +1,424 @@
+use cosmwasm_std::{attr, from_binary, to_binary, Uint128, Decimal, Reply, ContractResult, SubMsgExecutionResponse, Event, SubMsg, WasmMsg, OwnedDeps};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage};
+
+use starterra_token::ido::{InstantiateMsg as IdoInstantiateMsg, Phase, StatusResponse};
+use starterra_token::ido_factory::{ConfigResponse, CreateIdoMsg, ExecuteMsg, InstantiateMsg, QueryMsg, TemplateResponse, IdoResponse, IdosResponse, IdoStatus, IsParticipantResponse};
+use starterra_token::common::OrderBy;
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::testing::mock_querier::{mock_dependencies, WasmMockQuerier};
+use crate::errors::ContractError;
+
+fn ido_template() -> IdoInstantiateMsg {
+    IdoInstantiateMsg {
+        owner: String::from("owner0000"),
+        prefund_address: String::from("prefund0000"),
+        kyc_terms_vault_address: String::from("kyc0000"),
+        ido_token: String::from("token0000"),
+        ido_token_price: Uint128::from(1u128),
+        end_date: 0,
+        paused: false,
+        minimum_prefund: Uint128::from(100u128),
+        staking_gateway_address: String::from("gateway0000"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury0000"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    }
+}
+
+fn status(is_closed: bool, current_phase: Option<String>, time_to_next_phase: Option<u64>) -> StatusResponse {
+    StatusResponse {
+        is_closed,
+        is_paused: false,
+        snapshot_time: None,
+        is_cancelled: false,
+        current_phase,
+        time_to_next_phase,
+    }
+}
+
+fn instantiated_reply(id: u64, contract_address: &str) -> Reply {
+    Reply {
+        id,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![Event::new("instantiate_contract")
+                .add_attribute("code_id", "7")
+                .add_attribute("contract_address", contract_address)],
+            data: None,
+        }),
+    }
+}
+
+fn create_and_register_ido(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    label: &str,
+    contract_address: &str,
+) -> u64 {
+    let msg = ExecuteMsg::CreateIdo(CreateIdoMsg {
+        template: String::from("default"),
+        label: String::from(label),
+        ido_token: String::from("token0001"),
+        ido_token_price: Uint128::from(2u128),
+        end_date: 2000000000,
+        phases: None,
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let ido_id = res.messages[0].id;
+    reply(deps.as_mut(), mock_env(), instantiated_reply(ido_id, contract_address)).unwrap();
+    ido_id
+}
+
+fn init_with_template(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) {
+    let msg = InstantiateMsg {
+        owner: String::from("owner0000"),
+        ido_code_id: 7,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::StoreTemplate {
+        name: String::from("default"),
+        template: ido_template(),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner0000"),
+        ido_code_id: 7,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config,
+        ConfigResponse {
+            owner: String::from("owner0000"),
+            ido_code_id: 7,
+            number_of_idos: 0,
+        }
+    );
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies(&[]);
+    init_with_template(&mut deps);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: Some(String::from("owner0001")),
+        ido_code_id: Some(8),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    let msg = ExecuteMsg::AcceptOwnership {};
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0001", &[]), msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config,
+        ConfigResponse {
+            owner: String::from("owner0001"),
+            ido_code_id: 8,
+            number_of_idos: 0,
+        }
+    );
+
+    // Unauthorized err
+    let msg = ExecuteMsg::UpdateConfig { owner: None, ido_code_id: None };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg);
+    match res {
+        Err(ContractError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+}
+
+#[test]
+fn store_and_remove_template() {
+    let mut deps = mock_dependencies(&[]);
+    init_with_template(&mut deps);
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Template { name: String::from("default") }).unwrap();
+    let template: TemplateResponse = from_binary(&res).unwrap();
+    assert_eq!(template.template, ido_template());
+
+    // only owner can manage templates
+    let msg = ExecuteMsg::RemoveTemplate { name: String::from("default") };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg.clone()).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg);
+    match res {
+        Err(ContractError::TemplateNotFound {}) => {}
+        _ => panic!("Must return template not found error"),
+    }
+
+    let msg = ExecuteMsg::CreateIdo(CreateIdoMsg {
+        template: String::from("default"),
+        label: String::from("first sale"),
+        ido_token: String::from("token0001"),
+        ido_token_price: Uint128::from(2u128),
+        end_date: 2000000000,
+        phases: None,
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg);
+    match res {
+        Err(ContractError::TemplateNotFound {}) => {}
+        _ => panic!("Must return template not found error"),
+    }
+}
+
+#[test]
+fn create_ido() {
+    let mut deps = mock_dependencies(&[]);
+    init_with_template(&mut deps);
+
+    let phases = vec![Phase {
+        name: String::from("public"),
+        start_time: 1900000000,
+        end_time: 2000000000,
+        minimum_prefund: None,
+        stakers_only: false,
+        max_participants: None,
+    }];
+    let msg = ExecuteMsg::CreateIdo(CreateIdoMsg {
+        template: String::from("default"),
+        label: String::from("first sale"),
+        ido_token: String::from("token0001"),
+        ido_token_price: Uint128::from(2u128),
+        end_date: 2000000000,
+        phases: Some(phases.clone()),
+    });
+
+    // only owner can create sales
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let expected_msg = IdoInstantiateMsg {
+        ido_token: String::from("token0001"),
+        ido_token_price: Uint128::from(2u128),
+        end_date: 2000000000,
+        phases,
+        ..ido_template()
+    };
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Instantiate {
+                admin: Some(String::from("owner0000")),
+                code_id: 7,
+                msg: to_binary(&expected_msg).unwrap(),
+                funds: vec![],
+                label: String::from("first sale"),
+            },
+            1,
+        )]
+    );
+
+    // the new sale is indexed with the status it reports, upcoming until its first phase opens
+    deps.querier.with_ido_status(vec![
+        (String::from("ido0001"), status(false, None, Some(100))),
+    ]);
+
+    // reply for an unknown submessage is rejected
+    let res = reply(deps.as_mut(), mock_env(), instantiated_reply(2, "ido0001"));
+    match res {
+        Err(ContractError::UnknownReplyId {}) => {}
+        _ => panic!("Must return unknown reply id error"),
+    }
+
+    let _res = reply(deps.as_mut(), mock_env(), instantiated_reply(1, "ido0001")).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Ido { ido_id: 1 }).unwrap();
+    let ido: IdoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        ido,
+        IdoResponse {
+            ido_id: 1,
+            address: String::from("ido0001"),
+            label: String::from("first sale"),
+            template: String::from("default"),
+            created_at: mock_env().block.time.seconds(),
+            status: IdoStatus::Upcoming,
+        }
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.number_of_idos, 1);
+}
+
+#[test]
+fn query_idos_by_status() {
+    let mut deps = mock_dependencies(&[]);
+    init_with_template(&mut deps);
+
+    // sales are indexed with the status they report when registered, a sale without phases is live right away
+    deps.querier.with_ido_status(vec![
+        (String::from("ido0001"), status(false, None, Some(100))),
+        (String::from("ido0002"), status(false, Some(String::from("public")), None)),
+        (String::from("ido0003"), status(true, None, None)),
+        (String::from("ido0004"), status(false, None, None)),
+    ]);
+    create_and_register_ido(&mut deps, "upcoming sale", "ido0001");
+    create_and_register_ido(&mut deps, "live sale", "ido0002");
+    create_and_register_ido(&mut deps, "closed sale", "ido0003");
+    create_and_register_ido(&mut deps, "second live sale", "ido0004");
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Idos {
+        status: Some(IdoStatus::Live),
+        start_after: None,
+        limit: None,
+        order_by: Some(OrderBy::Asc),
+    }).unwrap();
+    let idos: IdosResponse = from_binary(&res).unwrap();
+    assert_eq!(idos.idos.iter().map(|ido| ido.ido_id).collect::<Vec<u64>>(), vec![2, 4]);
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Idos {
+        status: Some(IdoStatus::Live),
+        start_after: Some(2),
+        limit: None,
+        order_by: Some(OrderBy::Asc),
+    }).unwrap();
+    let idos: IdosResponse = from_binary(&res).unwrap();
+    assert_eq!(idos.idos.iter().map(|ido| ido.ido_id).collect::<Vec<u64>>(), vec![4]);
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Idos {
+        status: Some(IdoStatus::Closed),
+        start_after: None,
+        limit: None,
+        order_by: None,
+    }).unwrap();
+    let idos: IdosResponse = from_binary(&res).unwrap();
+    assert_eq!(idos.idos.len(), 1);
+    assert_eq!(idos.idos[0].label, "closed sale");
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Idos {
+        status: None,
+        start_after: Some(3),
+        limit: Some(1),
+        order_by: Some(OrderBy::Desc),
+    }).unwrap();
+    let idos: IdosResponse = from_binary(&res).unwrap();
+    assert_eq!(idos.idos.iter().map(|ido| ido.ido_id).collect::<Vec<u64>>(), vec![2]);
+}
+
+#[test]
+fn sync_ido_status() {
+    let mut deps = mock_dependencies(&[]);
+    init_with_template(&mut deps);
+
+    deps.querier.with_ido_status(vec![
+        (String::from("ido0001"), status(false, None, Some(100))),
+    ]);
+    create_and_register_ido(&mut deps, "first sale", "ido0001");
+
+    // the sale stays indexed as upcoming until its status is synced
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Idos {
+        status: Some(IdoStatus::Upcoming),
+        start_after: None,
+        limit: None,
+        order_by: None,
+    }).unwrap();
+    let idos: IdosResponse = from_binary(&res).unwrap();
+    assert_eq!(idos.idos.iter().map(|ido| ido.ido_id).collect::<Vec<u64>>(), vec![1]);
+
+    deps.querier.with_ido_status(vec![
+        (String::from("ido0001"), status(false, Some(String::from("public")), None)),
+    ]);
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), ExecuteMsg::SyncIdoStatus { ido_id: 1 }).unwrap();
+    assert_eq!(res.attributes, vec![
+        attr("action", "sync_ido_status"),
+        attr("ido_id", "1"),
+    ]);
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Idos {
+        status: Some(IdoStatus::Upcoming),
+        start_after: None,
+        limit: None,
+        order_by: None,
+    }).unwrap();
+    let idos: IdosResponse = from_binary(&res).unwrap();
+    assert!(idos.idos.is_empty());
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Ido { ido_id: 1 }).unwrap();
+    let ido: IdoResponse = from_binary(&res).unwrap();
+    assert_eq!(ido.status, IdoStatus::Live);
+
+    // unknown sales can not be synced
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), ExecuteMsg::SyncIdoStatus { ido_id: 2 });
+    assert!(res.is_err());
+}
+
+#[test]
+fn is_participant_in_any_ido() {
+    let mut deps = mock_dependencies(&[]);
+    init_with_template(&mut deps);
+
+    deps.querier.with_ido_status(vec![
+        (String::from("ido0001"), status(false, None, None)),
+        (String::from("ido0002"), status(false, None, None)),
+    ]);
+    create_and_register_ido(&mut deps, "first sale", "ido0001");
+    create_and_register_ido(&mut deps, "second sale", "ido0002");
+    deps.querier.with_ido_participants(vec![
+        (String::from("ido0002"), vec![String::from("addr0001")]),
+    ]);
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::IsParticipantInAnyIdo {
+        address: String::from("addr0001"),
+        start_after: None,
+        limit: None,
+    }).unwrap();
+    let participant: IsParticipantResponse = from_binary(&res).unwrap();
+    assert!(participant.is_participant);
+    assert_eq!(participant.last_ido_id, Some(2));
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::IsParticipantInAnyIdo {
+        address: String::from("addr0002"),
+        start_after: None,
+        limit: None,
+    }).unwrap();
+    let participant: IsParticipantResponse = from_binary(&res).unwrap();
+    assert!(!participant.is_participant);
+    assert_eq!(participant.last_ido_id, Some(2));
+
+    // the scan is bounded by the limit and continues from the last scanned sale
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::IsParticipantInAnyIdo {
+        address: String::from("addr0001"),
+        start_after: None,
+        limit: Some(1),
+    }).unwrap();
+    let participant: IsParticipantResponse = from_binary(&res).unwrap();
+    assert!(!participant.is_participant);
+    assert_eq!(participant.last_ido_id, Some(1));
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::IsParticipantInAnyIdo {
+        address: String::from("addr0001"),
+        start_after: participant.last_ido_id,
+        limit: Some(1),
+    }).unwrap();
+    let participant: IsParticipantResponse = from_binary(&res).unwrap();
+    assert!(participant.is_participant);
+}
@@ -0,0 +1,33 @@
+use cosmwasm_std::{Deps, MessageInfo, StdResult};
+use starterra_token::ido::StatusResponse;
+use starterra_token::ido_factory::IdoStatus;
+
+use crate::errors::ContractError;
+use crate::querier::load_ido_status;
+use crate::state::{read_config, IdoInfo};
+
+pub fn assert_owner_privilege(
+    deps: Deps,
+    info: &MessageInfo,
+) -> Result<(), ContractError> {
+    if read_config(deps.storage)?.owner != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+// A cancelled sale is closed, a sale waiting for its next phase to open is upcoming
+pub fn to_ido_status(status: &StatusResponse) -> IdoStatus {
+    if status.is_closed || status.is_cancelled {
+        IdoStatus::Closed
+    } else if status.current_phase.is_none() && status.time_to_next_phase.is_some() {
+        IdoStatus::Upcoming
+    } else {
+        IdoStatus::Live
+    }
+}
+
+pub fn fetch_ido_status(deps: Deps, ido: &IdoInfo) -> StdResult<IdoStatus> {
+    Ok(to_ido_status(&load_ido_status(deps, &ido.address)?))
+}
@@ -3,7 +3,8 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use starterra_token::ido::{ExecuteMsg, InstantiateMsg, QueryMsg, ConfigResponse, ParticipantInfoResponse, AllocationInfo, StateResponse, StatusResponse, ParticipantsResponse, ParticipantResponse};
+use starterra_token::ido::{ExecuteMsg, InstantiateMsg, QueryMsg, ConfigResponse, ParticipantInfoResponse, AllocationInfo, StateResponse, StatusResponse, ParticipantsResponse, ParticipantAccountsResponse, ParticipantResponse, LotteryResponse, LotteryResultResponse, ParticipantSnapshot, ReferralsResponse, ReferralLeaderboardResponse, Cw20HookMsg, EligibilityResponse, PrefundCreditResponse};
+use starterra_token::vesting::common::VestingAccountResponse;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -20,4 +21,14 @@ fn main() {
     export_schema(&schema_for!(AllocationInfo), &out_dir);
     export_schema(&schema_for!(ParticipantResponse), &out_dir);
     export_schema(&schema_for!(ParticipantsResponse), &out_dir);
+    export_schema(&schema_for!(ParticipantAccountsResponse), &out_dir);
+    export_schema(&schema_for!(LotteryResponse), &out_dir);
+    export_schema(&schema_for!(LotteryResultResponse), &out_dir);
+    export_schema(&schema_for!(VestingAccountResponse), &out_dir);
+    export_schema(&schema_for!(ParticipantSnapshot), &out_dir);
+    export_schema(&schema_for!(ReferralsResponse), &out_dir);
+    export_schema(&schema_for!(ReferralLeaderboardResponse), &out_dir);
+    export_schema(&schema_for!(Cw20HookMsg), &out_dir);
+    export_schema(&schema_for!(EligibilityResponse), &out_dir);
+    export_schema(&schema_for!(PrefundCreditResponse), &out_dir);
 }
@@ -1,12 +1,12 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{StdResult, Binary, to_binary, MessageInfo, Deps, DepsMut, Response, Env};
+use cosmwasm_std::{StdResult, Binary, to_binary, MessageInfo, Deps, DepsMut, Response, Env, Uint128, Reply, Decimal};
 
 use starterra_token::ido::{ExecuteMsg, MigrateMsg, InstantiateMsg, QueryMsg};
-use crate::state::{store_config, Config, store_state, State};
-use crate::execute::{update_config, join_ido, accept_ownership};
-use crate::tools::{assert_owner_privilege};
-use crate::queries::{query_config, query_participant, query_ido_state, query_ido_status, query_snapshot_time, query_participants};
+use crate::state::{store_config, Config, store_state, State, read_legacy_config};
+use crate::execute::{update_config, join_ido, leave_ido, accept_ownership, settle_allocations, claim_ido_tokens, commit_lottery_seed, reveal_lottery_seed, draw_lottery, cancel_ido, take_snapshot, prefund_native, receive_cw20, withdraw_prefund_credit, deposit_swapped_prefund, credit_prefund_deposit, forward_withdrawn_prefund_credit, PREFUND_REPLY_ID, PREFUND_DEPOSIT_REPLY_ID, PREFUND_WITHDRAW_REPLY_ID};
+use crate::tools::{assert_owner_privilege, assert_tiers, assert_phases, assert_vesting_schedule, assert_referral_share};
+use crate::queries::{query_config, query_participant, query_ido_state, query_ido_status, query_snapshot_time, query_participants, query_participant_accounts, query_allocation, query_lottery, query_lottery_result, query_claimable_at, query_snapshot, query_referrals, query_referral_leaderboard, query_eligibility, query_prefund_credit};
 use crate::errors::ContractError;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -20,6 +20,10 @@ pub fn instantiate(
     if msg.end_date <= env.block.time.seconds() {
         return Err(ContractError::EndDateInThePast {});
     }
+    assert_tiers(&msg.tiers)?;
+    assert_phases(&msg.phases, msg.end_date)?;
+    assert_vesting_schedule(&msg.vesting)?;
+    assert_referral_share(msg.referral_share)?;
 
     store_config(
         deps.storage,
@@ -34,6 +38,17 @@ pub fn instantiate(
             paused: msg.paused,
             snapshot_time: None,
             minimum_prefund: msg.minimum_prefund,
+            staking_gateway_address: deps.api.addr_canonicalize(&msg.staking_gateway_address)?,
+            tiers: msg.tiers,
+            allocation_per_weight: msg.allocation_per_weight,
+            soft_cap: msg.soft_cap,
+            phases: msg.phases,
+            hard_cap: msg.hard_cap,
+            vesting: msg.vesting,
+            leave_penalty: msg.leave_penalty,
+            treasury_address: deps.api.addr_canonicalize(&msg.treasury_address)?,
+            terraswap_router: deps.api.addr_canonicalize(&msg.terraswap_router)?,
+            referral_share: msg.referral_share,
         },
     )?;
 
@@ -41,6 +56,15 @@ pub fn instantiate(
         deps.storage,
         &State {
             number_of_participants: 0,
+            total_allocation: Uint128::zero(),
+            settlement_cursor: None,
+            is_settled: false,
+            lottery_entrants: 0,
+            total_committed: Uint128::zero(),
+            lottery_released: Uint128::zero(),
+            is_cancelled: false,
+            snapshot_participants: 0,
+            is_snapshot_taken: false,
         },
     )?;
 
@@ -55,8 +79,17 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg.clone() {
-        ExecuteMsg::JoinIdo {} => join_ido(deps, info.clone(), env),
+        ExecuteMsg::JoinIdo { referrer } => join_ido(deps, info.clone(), env, referrer),
+        ExecuteMsg::LeaveIdo {} => leave_ido(deps, info, env),
         ExecuteMsg::AcceptOwnership {} => { accept_ownership(deps, info) }
+        ExecuteMsg::ClaimIdoTokens {} => claim_ido_tokens(deps, env, info),
+        ExecuteMsg::DrawLottery { limit } => draw_lottery(deps, limit),
+        ExecuteMsg::TakeSnapshot { start_after, limit } => take_snapshot(deps, env, start_after, limit),
+        ExecuteMsg::Prefund { operations, minimum_receive } => {
+            prefund_native(deps, env, info, operations, minimum_receive)
+        }
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::WithdrawPrefundCredit {} => withdraw_prefund_credit(deps, env, info),
         _ => {
             assert_owner_privilege(deps.as_ref(), info.clone())?;
             match msg {
@@ -70,6 +103,17 @@ pub fn execute(
                     paused,
                     snapshot_time,
                     minimum_prefund,
+                    staking_gateway_address,
+                    tiers,
+                    allocation_per_weight,
+                    soft_cap,
+                    phases,
+                    hard_cap,
+                    vesting,
+                    leave_penalty,
+                    treasury_address,
+                    terraswap_router,
+                    referral_share,
                 } => {
                     update_config(
                         deps,
@@ -84,14 +128,49 @@ pub fn execute(
                         paused,
                         snapshot_time,
                         minimum_prefund,
+                        staking_gateway_address,
+                        tiers,
+                        allocation_per_weight,
+                        soft_cap,
+                        phases,
+                        hard_cap,
+                        vesting,
+                        leave_penalty,
+                        treasury_address,
+                        terraswap_router,
+                        referral_share,
                     )
                 }
+                ExecuteMsg::SettleAllocations { limit } => {
+                    settle_allocations(deps, env, limit)
+                }
+                ExecuteMsg::CommitLotterySeed { seed_hash, winning_tickets } => {
+                    commit_lottery_seed(deps, env, seed_hash, winning_tickets)
+                }
+                ExecuteMsg::RevealLotterySeed { seed } => {
+                    reveal_lottery_seed(deps, env, seed)
+                }
+                ExecuteMsg::CancelIdo {} => cancel_ido(deps, env),
                 _ => panic!("DO NOT ENTER HERE"),
             }
         }
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    deps: DepsMut,
+    env: Env,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    match msg.id {
+        PREFUND_REPLY_ID => deposit_swapped_prefund(deps, env),
+        PREFUND_DEPOSIT_REPLY_ID => credit_prefund_deposit(deps, env),
+        PREFUND_WITHDRAW_REPLY_ID => forward_withdrawn_prefund_credit(deps, env),
+        _ => Err(ContractError::UnknownReplyId {}),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(
     deps: Deps,
@@ -117,10 +196,81 @@ pub fn query(
         } => {
             to_binary(&query_participants(deps, start_after, limit, order_by)?)
         }
+        QueryMsg::ParticipantAccounts {
+            start_after,
+            limit,
+            order_by,
+        } => {
+            to_binary(&query_participant_accounts(deps, start_after, limit, order_by)?)
+        }
+        QueryMsg::Allocation { address } => {
+            to_binary(&query_allocation(deps, address)?)
+        }
+        QueryMsg::Lottery {} => to_binary(&query_lottery(deps)?),
+        QueryMsg::LotteryResult { address } => {
+            to_binary(&query_lottery_result(deps, address)?)
+        }
+        QueryMsg::ClaimableAt { address, block_time } => {
+            to_binary(&query_claimable_at(deps, env, address, block_time)?)
+        }
+        QueryMsg::Snapshot { address } => {
+            to_binary(&query_snapshot(deps, address)?)
+        }
+        QueryMsg::Referrals { address } => {
+            to_binary(&query_referrals(deps, address)?)
+        }
+        QueryMsg::ReferralLeaderboard {
+            start_after,
+            limit,
+            order_by,
+        } => {
+            to_binary(&query_referral_leaderboard(deps, start_after, limit, order_by)?)
+        }
+        QueryMsg::Eligibility { address, block_time } => {
+            to_binary(&query_eligibility(deps, env, address, block_time)?)
+        }
+        QueryMsg::PrefundCredit { address } => {
+            to_binary(&query_prefund_credit(deps, address)?)
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    if let Some(legacy_config) = read_legacy_config(deps.storage) {
+        let (staking_gateway_address, treasury_address, terraswap_router) =
+            match (msg.staking_gateway_address, msg.treasury_address, msg.terraswap_router) {
+                (Some(staking_gateway_address), Some(treasury_address), Some(terraswap_router)) => {
+                    (staking_gateway_address, treasury_address, terraswap_router)
+                }
+                _ => return Err(ContractError::LegacyConfigAddressMissing {}),
+            };
+
+        // everything added since keeps the behaviour of the legacy IDO until the owner configures it
+        store_config(deps.storage, &Config {
+            owner: legacy_config.owner,
+            pending_owner: legacy_config.pending_owner,
+            prefund_address: legacy_config.prefund_address,
+            kyc_terms_vault_address: legacy_config.kyc_terms_vault_address,
+            ido_token: legacy_config.ido_token,
+            ido_token_price: legacy_config.ido_token_price,
+            end_date: legacy_config.end_date,
+            paused: legacy_config.paused,
+            snapshot_time: legacy_config.snapshot_time,
+            minimum_prefund: legacy_config.minimum_prefund,
+            staking_gateway_address: deps.api.addr_canonicalize(&staking_gateway_address)?,
+            tiers: vec![],
+            allocation_per_weight: Uint128::zero(),
+            soft_cap: Uint128::zero(),
+            phases: vec![],
+            hard_cap: Uint128::zero(),
+            vesting: None,
+            leave_penalty: Uint128::zero(),
+            treasury_address: deps.api.addr_canonicalize(&treasury_address)?,
+            terraswap_router: deps.api.addr_canonicalize(&terraswap_router)?,
+            referral_share: Decimal::zero(),
+        })?;
+    }
+
     Ok(Response::default())
 }
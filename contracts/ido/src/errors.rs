@@ -41,4 +41,112 @@ pub enum ContractError {
 
     #[error("End date can not be in the past")]
     EndDateInThePast {},
+
+    #[error("IDO is not closed yet")]
+    IdoNotClosed {},
+
+    #[error("Snapshot time is not set or not reached yet")]
+    SnapshotNotReached {},
+
+    #[error("IDO token price can not be zero")]
+    IdoTokenPriceZero {},
+
+    #[error("Allocations are already settled")]
+    AlreadySettled {},
+
+    #[error("Allocations are not settled yet")]
+    NotSettled {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Tiers have to be sorted by min_bond ascending")]
+    InvalidTiers {},
+
+    #[error("Seed hash has to be a hex encoded sha256 hash")]
+    InvalidSeedHash {},
+
+    #[error("Lottery seed is already committed")]
+    LotterySeedAlreadyCommitted {},
+
+    #[error("Lottery seed is not committed")]
+    LotterySeedNotCommitted {},
+
+    #[error("Lottery seed is already revealed")]
+    LotterySeedAlreadyRevealed {},
+
+    #[error("Lottery seed can not be revealed in the block it was committed")]
+    LotteryRevealTooEarly {},
+
+    #[error("Lottery seed does not match the committed hash")]
+    LotterySeedMismatch {},
+
+    #[error("Lottery seed is not revealed")]
+    LotterySeedNotRevealed {},
+
+    #[error("Lottery is already drawn")]
+    LotteryAlreadyDrawn {},
+
+    #[error("Lottery is not drawn yet")]
+    LotteryNotDrawn {},
+
+    #[error("IDO is cancelled")]
+    IdoCancelled {},
+
+    #[error("Allocations settlement has already started")]
+    SettlementStarted {},
+
+    #[error("Phases have to be ordered, not overlapping and end before the end date")]
+    InvalidPhases {},
+
+    #[error("There is no active IDO phase")]
+    NoActivePhase {},
+
+    #[error("Current IDO phase is full")]
+    PhaseFull {},
+
+    #[error("IDO hard cap is reached")]
+    HardCapReached {},
+
+    #[error("TGE unlock can not be greater than 1")]
+    InvalidVestingSchedule {},
+
+    #[error("You did not join the IDO")]
+    NotJoined {},
+
+    #[error("Snapshot time is reached, participants can not be changed")]
+    SnapshotTimeReached {},
+
+    #[error("Snapshot is already being taken")]
+    SnapshotStarted {},
+
+    #[error("Snapshot is not taken for every participant yet")]
+    SnapshotNotTaken {},
+
+    #[error("Swap operations have to start with the sent asset and end with uusd")]
+    InvalidSwapOperations {},
+
+    #[error("Exactly one native coin has to be sent")]
+    InvalidPrefundFunds {},
+
+    #[error("Swap returned no uusd to prefund")]
+    NoSwappedPrefund {},
+
+    #[error("Prefund deposit was not credited")]
+    NoPrefundCredit {},
+
+    #[error("Prefund credit is locked until the IDO you joined is settled")]
+    PrefundCreditLocked {},
+
+    #[error("Unknown reply id")]
+    UnknownReplyId {},
+
+    #[error("Referrer has to be a KYC verified IDO participant")]
+    InvalidReferrer {},
+
+    #[error("Referral share can not be greater than 1")]
+    InvalidReferralShare {},
+
+    #[error("Staking gateway, treasury and terraswap router addresses are required to migrate the legacy config")]
+    LegacyConfigAddressMissing {},
 }
@@ -1,12 +1,29 @@
-use cosmwasm_std::{Uint128, DepsMut, MessageInfo, Env, Response};
-use crate::state::{read_config, store_config, read_state, store_state, Config, read_participant, store_participant};
-use crate::querier::{load_user_prefund_balance, check_user_kyc_terms_verified};
+use cosmwasm_std::{Uint128, Decimal, CanonicalAddr, DepsMut, MessageInfo, Env, Response, CosmosMsg, WasmMsg, BankMsg, Coin, SubMsg, attr, to_binary, from_binary};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use terraswap::asset::{Asset, AssetInfo};
+use terraswap::querier::query_balance;
+use terraswap::router::{SwapOperation, ExecuteMsg as RouterExecuteMsg, Cw20HookMsg as RouterCw20HookMsg};
+use starterra_token::common::{OrderBy, assert_sent_native_token_balance, get_sent_native_token_amount};
+use starterra_token::ido::{ParticipantInfoResponse, ParticipantSnapshot, Cw20HookMsg};
+use starterra_token::ido_prefund::{ExecuteMsg as PrefundExecuteMsg, FunderRequest};
+use crate::state::{read_config, store_config, read_state, store_state, Config, read_participant, store_participant, read_participants, read_ido_participant, store_ido_participant, Lottery, read_lottery, store_lottery, read_lottery_result, store_lottery_result, read_phase_participants, store_phase_participants, remove_participant, read_snapshot, store_snapshot, PendingPrefund, store_pending_prefund, read_pending_prefund, remove_pending_prefund, store_referral, read_referral, remove_referral, read_referrer, store_referrer, read_prefund_credit, store_prefund_credit};
+use starterra_token::ido::{Tier, Phase, VestingSchedule};
+use crate::querier::{load_user_prefund_balance, check_user_kyc_terms_verified, load_user_bond_amount};
+use crate::tools::{assert_referral_share, assert_swap_operations, ust_asset_info, compute_unlocked_amount, assert_vesting_schedule, is_cancelled, assert_phases, find_phase, compute_ido_token_amount, compute_allocation, assert_tiers, find_tier, sha256_hex, lottery_ticket};
 use crate::errors::ContractError;
 
+const DEFAULT_SETTLEMENT_LIMIT: u32 = 30;
+const DEFAULT_DRAW_LIMIT: u32 = 100;
+const DEFAULT_SNAPSHOT_LIMIT: u32 = 30;
+pub const PREFUND_REPLY_ID: u64 = 1;
+pub const PREFUND_DEPOSIT_REPLY_ID: u64 = 2;
+pub const PREFUND_WITHDRAW_REPLY_ID: u64 = 3;
+
 pub fn join_ido(
     deps: DepsMut,
     info: MessageInfo,
     env: Env,
+    referrer: Option<String>,
 ) -> Result<Response, ContractError> {
     let sender = deps.api.addr_canonicalize(&info.sender.clone().into_string())?;
     let mut ido_participant =
@@ -23,18 +40,47 @@ pub fn join_ido(
     if config.paused {
         return Err(ContractError::IdoPaused {});
     }
-    if env.block.time.seconds() > config.end_date {
+    let block_time = env.block.time.seconds();
+    if block_time > config.end_date {
         return Err(ContractError::IdoClosed {});
     }
+    if matches!(config.snapshot_time, Some(snapshot_time) if snapshot_time <= block_time) {
+        return Err(ContractError::SnapshotTimeReached {});
+    }
+    let mut state = read_state(deps.storage)?;
+    if state.is_cancelled {
+        return Err(ContractError::IdoCancelled {});
+    }
+
+    // without phases the sale is open for everyone until end_date
+    let mut minimum_prefund = config.minimum_prefund;
+    let mut stakers_only = false;
+    if !config.phases.is_empty() {
+        let (index, phase) = match find_phase(&config.phases, block_time) {
+            Some(found) => found,
+            None => return Err(ContractError::NoActivePhase {}),
+        };
+        let phase_participants = read_phase_participants(deps.storage, index)?;
+        if let Some(max_participants) = phase.max_participants {
+            if phase_participants >= max_participants {
+                return Err(ContractError::PhaseFull {});
+            }
+        }
+        store_phase_participants(deps.storage, index, phase_participants + 1)?;
+
+        minimum_prefund = phase.minimum_prefund.unwrap_or(config.minimum_prefund);
+        stakers_only = phase.stakers_only;
+        ido_participant.phase = Some(index);
+    }
 
-    let funder_info = load_user_prefund_balance(
+    let available_funds = load_user_prefund_balance(
         &deps.querier,
         deps.api.addr_humanize(&config.prefund_address)?.into_string(),
         info.sender.clone().into_string(),
-    )?;
+    )?.available_funds + read_prefund_credit(deps.storage, &sender)?;
 
     // check if deposited enough
-    if funder_info.available_funds < config.minimum_prefund {
+    if available_funds < minimum_prefund {
         return Err(ContractError::NotEnoughDeposit {});
     }
 
@@ -47,16 +93,148 @@ pub fn join_ido(
         return Err(ContractError::TouFailed {});
     }
 
-    let mut state = read_state(deps.storage)?;
+    // only participants that passed KYC themselves can refer others
+    let referrer = match referrer {
+        Some(referrer) => {
+            let referrer_raw = deps.api.addr_canonicalize(&referrer)?;
+            if referrer_raw == sender || !read_participant(deps.storage, &referrer_raw)?.is_joined {
+                return Err(ContractError::InvalidReferrer {});
+            }
+            let referrer_kyc_terms_info = check_user_kyc_terms_verified(&deps.querier, deps.api.addr_humanize(&config.kyc_terms_vault_address)?.into_string(), referrer.clone())?;
+            if !referrer_kyc_terms_info.is_verified || !referrer_kyc_terms_info.is_accepted {
+                return Err(ContractError::InvalidReferrer {});
+            }
+            Some((referrer, referrer_raw))
+        }
+        None => None,
+    };
+
+    // tiers are optional, without them allocation is only limited by the prefund balance
+    if !config.tiers.is_empty() || stakers_only {
+        let bond_info = load_user_bond_amount(
+            &deps.querier,
+            deps.api.addr_humanize(&config.staking_gateway_address)?.into_string(),
+            info.sender.clone().into_string(),
+        )?;
+        if stakers_only && bond_info.bond_amount.is_zero() {
+            return Err(ContractError::NotEligibleToJoinIdo {});
+        }
+        if !config.tiers.is_empty() {
+            match find_tier(&config.tiers, bond_info.bond_amount) {
+                Some(tier) => {
+                    ido_participant.tier = Some(tier.tier);
+                    ido_participant.weight = tier.weight;
+                    ido_participant.lottery = tier.lottery;
+                }
+                None => return Err(ContractError::NotEligibleToJoinIdo {}),
+            }
+        }
+    }
+
+    ido_participant.committed = compute_allocation(&config, &ido_participant, available_funds);
+    // the participant that reaches the hard cap is filled partially
+    if !config.hard_cap.is_zero() {
+        if state.total_committed >= config.hard_cap {
+            return Err(ContractError::HardCapReached {});
+        }
+        ido_participant.committed = std::cmp::min(ido_participant.committed, config.hard_cap - state.total_committed);
+    }
+
     state.number_of_participants += 1;
+    state.total_committed += ido_participant.committed;
+    if ido_participant.lottery {
+        state.lottery_entrants += 1;
+    }
     store_state(deps.storage, &state)?;
 
     ido_participant.is_joined = true;
     store_participant(deps.storage, &sender, &ido_participant)?;
 
+    let mut attributes = vec![
+        attr("action", "join_ido"),
+        attr("address", info.sender.into_string()),
+    ];
+    if let Some((referrer, referrer_raw)) = referrer {
+        store_referral(deps.storage, &sender, &referrer_raw)?;
+        let mut referrer_info = read_referrer(deps.storage, &referrer_raw)?;
+        referrer_info.referees += 1;
+        store_referrer(deps.storage, &referrer_raw, &referrer_info)?;
+        attributes.push(attr("referrer", referrer));
+    }
+
+    Ok(Response::new().add_attributes(attributes))
+}
+
+pub fn leave_ido(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let ido_participant = read_participant(deps.storage, &sender)?;
+    if !ido_participant.is_joined {
+        return Err(ContractError::NotJoined {});
+    }
+
+    let config = read_config(deps.storage)?;
+    if config.paused {
+        return Err(ContractError::IdoPaused {});
+    }
+    let block_time = env.block.time.seconds();
+    if block_time > config.end_date {
+        return Err(ContractError::UserJoinedAndCantBeEdited {});
+    }
+    if matches!(config.snapshot_time, Some(snapshot_time) if snapshot_time <= block_time) {
+        return Err(ContractError::SnapshotTimeReached {});
+    }
+    let mut state = read_state(deps.storage)?;
+    if state.is_cancelled {
+        return Err(ContractError::IdoCancelled {});
+    }
+    assert_sent_native_token_balance(&info, config.leave_penalty)?;
+
+    state.number_of_participants -= 1;
+    state.total_committed = state.total_committed.checked_sub(ido_participant.committed).unwrap_or_default();
+    if ido_participant.lottery {
+        state.lottery_entrants -= 1;
+    }
+    store_state(deps.storage, &state)?;
+
+    // leaving frees the slot in the phase the participant joined in
+    if let Some(phase) = ido_participant.phase {
+        let phase_participants = read_phase_participants(deps.storage, phase)?;
+        store_phase_participants(deps.storage, phase, phase_participants.saturating_sub(1))?;
+    }
+    remove_participant(deps.storage, &sender);
+
+    if let Some(referrer) = read_referral(deps.storage, &sender)? {
+        let mut referrer_info = read_referrer(deps.storage, &referrer)?;
+        referrer_info.referees = referrer_info.referees.saturating_sub(1);
+        store_referrer(deps.storage, &referrer, &referrer_info)?;
+        remove_referral(deps.storage, &sender);
+    }
+
+    // penalty goes to the treasury, anything sent above it is returned
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !config.leave_penalty.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: deps.api.addr_humanize(&config.treasury_address)?.into_string(),
+            amount: vec![Coin { denom: String::from("uusd"), amount: config.leave_penalty }],
+        }));
+    }
+    let refund = get_sent_native_token_amount(&info) - config.leave_penalty;
+    if !refund.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.clone().into_string(),
+            amount: vec![Coin { denom: String::from("uusd"), amount: refund }],
+        }));
+    }
+
     Ok(Response::new()
-        .add_attribute("action", "join_ido")
-        .add_attribute("address", info.sender.into_string()))
+        .add_messages(messages)
+        .add_attribute("action", "leave_ido")
+        .add_attribute("address", info.sender)
+        .add_attribute("penalty", config.leave_penalty))
 }
 
 pub fn accept_ownership(
@@ -98,6 +276,17 @@ pub fn update_config(
     paused: Option<bool>,
     snapshot_time: Option<u64>,
     minimum_prefund: Option<Uint128>,
+    staking_gateway_address: Option<String>,
+    tiers: Option<Vec<Tier>>,
+    allocation_per_weight: Option<Uint128>,
+    soft_cap: Option<Uint128>,
+    phases: Option<Vec<Phase>>,
+    hard_cap: Option<Uint128>,
+    vesting: Option<VestingSchedule>,
+    leave_penalty: Option<Uint128>,
+    treasury_address: Option<String>,
+    terraswap_router: Option<String>,
+    referral_share: Option<Decimal>,
 ) -> Result<Response, ContractError> {
     let mut config = read_config(deps.storage)?;
 
@@ -140,11 +329,719 @@ pub fn update_config(
         if env.block.time.seconds() > snapshot_time {
             return Err(ContractError::SnapshotTimeFromPast {});
         }
+        if read_state(deps.storage)?.snapshot_participants > 0 {
+            return Err(ContractError::SnapshotStarted {});
+        }
         config.snapshot_time = Some(snapshot_time);
     }
 
+    if let Some(staking_gateway_address) = staking_gateway_address {
+        config.staking_gateway_address = deps.api.addr_canonicalize(&staking_gateway_address)?;
+    }
+
+    if let Some(tiers) = tiers {
+        assert_tiers(&tiers)?;
+        config.tiers = tiers;
+    }
+
+    if let Some(allocation_per_weight) = allocation_per_weight {
+        config.allocation_per_weight = allocation_per_weight;
+    }
+
+    if let Some(soft_cap) = soft_cap {
+        let state = read_state(deps.storage)?;
+        if state.is_settled || state.settlement_cursor.is_some() {
+            return Err(ContractError::SettlementStarted {});
+        }
+        config.soft_cap = soft_cap;
+    }
+
+    if let Some(phases) = phases {
+        config.phases = phases;
+    }
+    assert_phases(&config.phases, config.end_date)?;
+
+    if let Some(hard_cap) = hard_cap {
+        config.hard_cap = hard_cap;
+    }
+
+    if let Some(vesting) = vesting {
+        let vesting = Some(vesting);
+        assert_vesting_schedule(&vesting)?;
+        config.vesting = vesting;
+    }
+
+    if let Some(leave_penalty) = leave_penalty {
+        config.leave_penalty = leave_penalty;
+    }
+
+    if let Some(treasury_address) = treasury_address {
+        config.treasury_address = deps.api.addr_canonicalize(&treasury_address)?;
+    }
+
+    if let Some(terraswap_router) = terraswap_router {
+        config.terraswap_router = deps.api.addr_canonicalize(&terraswap_router)?;
+    }
+
+    if let Some(referral_share) = referral_share {
+        assert_referral_share(referral_share)?;
+        config.referral_share = referral_share;
+    }
+
     store_config(deps.storage, &config)?;
 
     Ok(Response::new()
         .add_attribute("action", "update_config"))
 }
+
+pub fn settle_allocations(
+    deps: DepsMut,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    let mut state = read_state(deps.storage)?;
+    let block_time = env.block.time.seconds();
+
+    if state.is_settled {
+        return Err(ContractError::AlreadySettled {});
+    }
+    if block_time <= config.end_date {
+        return Err(ContractError::IdoNotClosed {});
+    }
+    if is_cancelled(&config, &state, block_time) {
+        return Err(ContractError::IdoCancelled {});
+    }
+    match config.snapshot_time {
+        Some(snapshot_time) if snapshot_time <= block_time => {}
+        _ => return Err(ContractError::SnapshotNotReached {}),
+    }
+    if !state.is_snapshot_taken {
+        return Err(ContractError::SnapshotNotTaken {});
+    }
+    if state.lottery_entrants > 0 && !matches!(read_lottery(deps.storage)?, Some(lottery) if lottery.is_drawn) {
+        return Err(ContractError::LotteryNotDrawn {});
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_SETTLEMENT_LIMIT);
+    let participants = read_participants(
+        deps.storage,
+        state.settlement_cursor.clone(),
+        Some(limit),
+        Some(OrderBy::Asc),
+    )?;
+
+    let prefund_address = deps.api.addr_humanize(&config.prefund_address)?.into_string();
+    let mut funder_reqs: Vec<FunderRequest> = vec![];
+    let mut total_paid_from_credit = Uint128::zero();
+    for (participant, participant_info) in participants.iter() {
+        let address = deps.api.addr_humanize(participant)?.into_string();
+        let snapshot = match read_snapshot(deps.storage, participant)? {
+            Some(snapshot) => snapshot,
+            None => return Err(ContractError::SnapshotNotTaken {}),
+        };
+
+        // unbonding after joining can only lower the tier
+        let mut participant_info = participant_info.clone();
+        if participant_info.tier.is_some() {
+            participant_info.weight = match find_tier(&config.tiers, snapshot.bond_amount) {
+                Some(tier) => std::cmp::min(tier.weight, participant_info.weight),
+                None => 0,
+            };
+        }
+
+        let is_lottery_loser = participant_info.lottery
+            && !read_lottery_result(deps.storage, participant)?.unwrap_or(false);
+        // snapshots read live balances when taken, the commitment at join time bounds the allocation
+        let allocation = if is_lottery_loser || !snapshot.is_verified || !snapshot.is_accepted {
+            Uint128::zero()
+        } else {
+            std::cmp::min(participant_info.committed, compute_allocation(&config, &participant_info, snapshot.available_funds))
+        };
+
+        store_ido_participant(
+            deps.storage,
+            participant,
+            &ParticipantInfoResponse {
+                allocation,
+                is_joined: true,
+                ido_token_amount: compute_ido_token_amount(allocation, config.ido_token_price)?,
+                claimed_amount: Uint128::zero(),
+            },
+        )?;
+
+        // a referrer that left the IDO is no longer a participant and accrues nothing
+        if !allocation.is_zero() && !config.referral_share.is_zero() {
+            if let Some(referrer) = read_referral(deps.storage, participant)? {
+                if read_participant(deps.storage, &referrer)?.is_joined {
+                    let mut referrer_info = read_referrer(deps.storage, &referrer)?;
+                    referrer_info.reward += allocation * config.referral_share;
+                    store_referrer(deps.storage, &referrer, &referrer_info)?;
+                }
+            }
+        }
+
+        if !allocation.is_zero() {
+            state.total_allocation += allocation;
+            // prefund credit is held by the IDO contract on the prefund, so that share is paid from its balance
+            let prefund_credit = read_prefund_credit(deps.storage, participant)?;
+            let paid_from_credit = std::cmp::min(allocation, prefund_credit);
+            if !paid_from_credit.is_zero() {
+                store_prefund_credit(deps.storage, participant, prefund_credit - paid_from_credit)?;
+                total_paid_from_credit += paid_from_credit;
+            }
+            if allocation > paid_from_credit {
+                funder_reqs.push(FunderRequest {
+                    addr: address,
+                    amount: allocation - paid_from_credit,
+                });
+            }
+        }
+    }
+    if !total_paid_from_credit.is_zero() {
+        funder_reqs.push(FunderRequest {
+            addr: env.contract.address.into_string(),
+            amount: total_paid_from_credit,
+        });
+    }
+
+    if let Some((last, _)) = participants.last() {
+        state.settlement_cursor = Some(last.clone());
+    }
+    if participants.len() < limit as usize {
+        state.is_settled = true;
+    }
+    store_state(deps.storage, &state)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !funder_reqs.is_empty() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: prefund_address,
+            funds: vec![],
+            msg: to_binary(&PrefundExecuteMsg::PayForIdo { funder_reqs })?,
+        }));
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "settle_allocations")
+        .add_attribute("settled_participants", participants.len().to_string())
+        .add_attribute("is_settled", state.is_settled.to_string()))
+}
+
+pub fn claim_ido_tokens(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    if !read_state(deps.storage)?.is_settled {
+        return Err(ContractError::NotSettled {});
+    }
+
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let mut participant_info = read_ido_participant(deps.storage, &sender)?;
+    let unlocked_amount = compute_unlocked_amount(&config.vesting, participant_info.ido_token_amount, env.block.time.seconds());
+    let claim_amount = unlocked_amount.checked_sub(participant_info.claimed_amount).unwrap_or_default();
+    if claim_amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    participant_info.claimed_amount = unlocked_amount;
+    store_ido_participant(deps.storage, &sender, &participant_info)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.ido_token)?.into_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.clone().into_string(),
+                amount: claim_amount,
+            })?,
+        }))
+        .add_attribute("action", "claim_ido_tokens")
+        .add_attribute("address", info.sender)
+        .add_attribute("amount", claim_amount))
+}
+
+pub fn commit_lottery_seed(
+    deps: DepsMut,
+    env: Env,
+    seed_hash: String,
+    winning_tickets: u64,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    let block_time = env.block.time.seconds();
+    if block_time <= config.end_date {
+        return Err(ContractError::IdoNotClosed {});
+    }
+    let state = read_state(deps.storage)?;
+    if is_cancelled(&config, &state, block_time) {
+        return Err(ContractError::IdoCancelled {});
+    }
+    if read_lottery(deps.storage)?.is_some() {
+        return Err(ContractError::LotterySeedAlreadyCommitted {});
+    }
+
+    let seed_hash = seed_hash.to_lowercase();
+    if seed_hash.len() != 64 || hex::decode(&seed_hash).is_err() {
+        return Err(ContractError::InvalidSeedHash {});
+    }
+
+    store_lottery(deps.storage, &Lottery {
+        seed_hash: seed_hash.clone(),
+        seed: None,
+        committed_at: block_time,
+        winning_tickets,
+        remaining_entrants: state.lottery_entrants,
+        remaining_tickets: winning_tickets,
+        cursor: None,
+        is_drawn: false,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "commit_lottery_seed")
+        .add_attribute("seed_hash", seed_hash)
+        .add_attribute("winning_tickets", winning_tickets.to_string())
+        .add_attribute("entrants", state.lottery_entrants.to_string()))
+}
+
+pub fn reveal_lottery_seed(
+    deps: DepsMut,
+    env: Env,
+    seed: String,
+) -> Result<Response, ContractError> {
+    let mut lottery = match read_lottery(deps.storage)? {
+        Some(lottery) => lottery,
+        None => return Err(ContractError::LotterySeedNotCommitted {}),
+    };
+    if lottery.seed.is_some() {
+        return Err(ContractError::LotterySeedAlreadyRevealed {});
+    }
+    if env.block.time.seconds() <= lottery.committed_at {
+        return Err(ContractError::LotteryRevealTooEarly {});
+    }
+    if sha256_hex(seed.as_bytes()) != lottery.seed_hash {
+        return Err(ContractError::LotterySeedMismatch {});
+    }
+
+    lottery.seed = Some(seed.clone());
+    store_lottery(deps.storage, &lottery)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "reveal_lottery_seed")
+        .add_attribute("seed", seed))
+}
+
+pub fn draw_lottery(
+    deps: DepsMut,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let mut lottery = match read_lottery(deps.storage)? {
+        Some(lottery) => lottery,
+        None => return Err(ContractError::LotterySeedNotCommitted {}),
+    };
+    let seed = match lottery.seed.clone() {
+        Some(seed) => seed,
+        None => return Err(ContractError::LotterySeedNotRevealed {}),
+    };
+    if lottery.is_drawn {
+        return Err(ContractError::LotteryAlreadyDrawn {});
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_DRAW_LIMIT);
+    let participants = read_participants(
+        deps.storage,
+        lottery.cursor.clone(),
+        Some(limit),
+        Some(OrderBy::Asc),
+    )?;
+
+    let mut state = read_state(deps.storage)?;
+    let mut winners = 0u64;
+    for (participant, participant_info) in participants.iter() {
+        if !participant_info.lottery || lottery.remaining_entrants == 0 {
+            continue;
+        }
+
+        // selection sampling: each entrant wins with probability remaining_tickets / remaining_entrants
+        let address = deps.api.addr_humanize(participant)?.into_string();
+        let is_winner = lottery_ticket(&seed, &address) % lottery.remaining_entrants < lottery.remaining_tickets;
+        store_lottery_result(deps.storage, participant, is_winner)?;
+
+        // commitments of losers no longer count towards the raised amount, the soft cap
+        // keeps using total_committed so the draw cannot cancel a sale that met it
+        if is_winner {
+            lottery.remaining_tickets -= 1;
+            winners += 1;
+        } else {
+            state.lottery_released += participant_info.committed;
+        }
+        lottery.remaining_entrants -= 1;
+    }
+
+    if let Some((last, _)) = participants.last() {
+        lottery.cursor = Some(last.clone());
+    }
+    if participants.len() < limit as usize {
+        lottery.is_drawn = true;
+    }
+    store_lottery(deps.storage, &lottery)?;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "draw_lottery")
+        .add_attribute("winners", winners.to_string())
+        .add_attribute("is_drawn", lottery.is_drawn.to_string()))
+}
+
+// Prefund balances are only debited by PayForIdo during settlement, so once the sale
+// is cancelled every committed amount simply stays available on the prefund contract.
+pub fn cancel_ido(
+    deps: DepsMut,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    let mut state = read_state(deps.storage)?;
+    if is_cancelled(&config, &state, env.block.time.seconds()) {
+        return Err(ContractError::IdoCancelled {});
+    }
+    if state.is_settled || state.settlement_cursor.is_some() {
+        return Err(ContractError::SettlementStarted {});
+    }
+
+    state.is_cancelled = true;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_ido")
+        .add_attribute("released", state.total_committed))
+}
+
+pub fn take_snapshot(
+    deps: DepsMut,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    match config.snapshot_time {
+        Some(snapshot_time) if snapshot_time <= env.block.time.seconds() => {}
+        _ => return Err(ContractError::SnapshotNotReached {}),
+    }
+
+    let mut state = read_state(deps.storage)?;
+    let start_after = match start_after {
+        Some(start_after) => Some(deps.api.addr_canonicalize(&start_after)?),
+        None => None,
+    };
+    let participants = read_participants(
+        deps.storage,
+        start_after,
+        Some(limit.unwrap_or(DEFAULT_SNAPSHOT_LIMIT)),
+        Some(OrderBy::Asc),
+    )?;
+
+    let prefund_address = deps.api.addr_humanize(&config.prefund_address)?.into_string();
+    let kyc_terms_vault_address = deps.api.addr_humanize(&config.kyc_terms_vault_address)?.into_string();
+    let staking_gateway_address = deps.api.addr_humanize(&config.staking_gateway_address)?.into_string();
+    let mut snapshot_participants = 0u64;
+    for (participant, participant_info) in participants.iter() {
+        // snapshot is taken only once per participant
+        if read_snapshot(deps.storage, participant)?.is_some() {
+            continue;
+        }
+
+        let address = deps.api.addr_humanize(participant)?.into_string();
+        let available_funds = load_user_prefund_balance(&deps.querier, prefund_address.clone(), address.clone())?.available_funds
+            + read_prefund_credit(deps.storage, participant)?;
+        let kyc_terms_info = check_user_kyc_terms_verified(&deps.querier, kyc_terms_vault_address.clone(), address.clone())?;
+        let bond_amount = if participant_info.tier.is_some() {
+            load_user_bond_amount(&deps.querier, staking_gateway_address.clone(), address)?.bond_amount
+        } else {
+            Uint128::zero()
+        };
+
+        store_snapshot(deps.storage, participant, &ParticipantSnapshot {
+            available_funds,
+            is_verified: kyc_terms_info.is_verified,
+            is_accepted: kyc_terms_info.is_accepted,
+            bond_amount,
+        })?;
+        snapshot_participants += 1;
+    }
+
+    state.snapshot_participants += snapshot_participants;
+    state.is_snapshot_taken = state.snapshot_participants == state.number_of_participants;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "take_snapshot")
+        .add_attribute("snapshot_participants", snapshot_participants.to_string())
+        .add_attribute("is_snapshot_taken", state.is_snapshot_taken.to_string()))
+}
+
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    match from_binary(&cw20_msg.msg)? {
+        Cw20HookMsg::Prefund { operations, minimum_receive } => {
+            let offer_asset = Asset {
+                info: AssetInfo::Token { contract_addr: info.sender.into_string() },
+                amount: cw20_msg.amount,
+            };
+            prefund(deps, env, cw20_msg.sender, offer_asset, operations, minimum_receive)
+        }
+    }
+}
+
+pub fn prefund_native(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operations: Vec<SwapOperation>,
+    minimum_receive: Uint128,
+) -> Result<Response, ContractError> {
+    if info.funds.len() != 1 {
+        return Err(ContractError::InvalidPrefundFunds {});
+    }
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken { denom: info.funds[0].denom.clone() },
+        amount: info.funds[0].amount,
+    };
+    prefund(deps, env, info.sender.into_string(), offer_asset, operations, minimum_receive)
+}
+
+// uusd is deposited right away, any other asset is swapped first and deposited in the reply.
+// The prefund only credits the sender of Deposit, so the IDO deposits under its own address
+// and keeps the deposit as prefund credit of the sender
+fn prefund(
+    deps: DepsMut,
+    env: Env,
+    sender: String,
+    offer_asset: Asset,
+    operations: Vec<SwapOperation>,
+    minimum_receive: Uint128,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    if config.paused {
+        return Err(ContractError::IdoPaused {});
+    }
+    let block_time = env.block.time.seconds();
+    if block_time > config.end_date {
+        return Err(ContractError::IdoClosed {});
+    }
+    if is_cancelled(&config, &read_state(deps.storage)?, block_time) {
+        return Err(ContractError::IdoCancelled {});
+    }
+    if offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidPrefundFunds {});
+    }
+
+    let sender_raw = deps.api.addr_canonicalize(&sender)?;
+    if operations.is_empty() && offer_asset.info.equal(&ust_asset_info()) {
+        if offer_asset.amount < minimum_receive {
+            return Err(ContractError::InvalidPrefundFunds {});
+        }
+        return Ok(Response::new()
+            .add_submessage(deposit_prefund_msg(deps, env, sender_raw, offer_asset.amount)?)
+            .add_attribute("action", "prefund")
+            .add_attribute("address", sender)
+            .add_attribute("amount", offer_asset.amount));
+    }
+    assert_swap_operations(&offer_asset.info, &operations)?;
+
+    store_pending_prefund(deps.storage, &PendingPrefund {
+        address: sender_raw,
+        ust_balance: query_balance(&deps.querier, env.contract.address, String::from("uusd"))?,
+        prefund_balance: Uint128::zero(),
+    })?;
+
+    let router = deps.api.addr_humanize(&config.terraswap_router)?.into_string();
+    let swap_msg = match offer_asset.info.clone() {
+        AssetInfo::NativeToken { denom } => WasmMsg::Execute {
+            contract_addr: router,
+            msg: to_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                operations,
+                minimum_receive: Some(minimum_receive),
+                to: None,
+            })?,
+            funds: vec![Coin { denom, amount: offer_asset.amount }],
+        },
+        AssetInfo::Token { contract_addr } => WasmMsg::Execute {
+            contract_addr,
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: router,
+                amount: offer_asset.amount,
+                msg: to_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                    operations,
+                    minimum_receive: Some(minimum_receive),
+                    to: None,
+                })?,
+            })?,
+            funds: vec![],
+        },
+    };
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(swap_msg, PREFUND_REPLY_ID))
+        .add_attribute("action", "swap_for_prefund")
+        .add_attribute("address", sender)
+        .add_attribute("offer_asset", offer_asset.to_string()))
+}
+
+pub fn deposit_swapped_prefund(
+    deps: DepsMut,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let pending_prefund = match read_pending_prefund(deps.storage)? {
+        Some(pending_prefund) => pending_prefund,
+        None => return Err(ContractError::UnknownReplyId {}),
+    };
+    remove_pending_prefund(deps.storage);
+
+    let ust_balance = query_balance(&deps.querier, env.contract.address.clone(), String::from("uusd"))?;
+    let received = ust_balance.checked_sub(pending_prefund.ust_balance).unwrap_or_default();
+    if received.is_zero() {
+        return Err(ContractError::NoSwappedPrefund {});
+    }
+    let address = deps.api.addr_humanize(&pending_prefund.address)?.into_string();
+
+    Ok(Response::new()
+        .add_submessage(deposit_prefund_msg(deps, env, pending_prefund.address, received)?)
+        .add_attribute("action", "prefund")
+        .add_attribute("address", address)
+        .add_attribute("amount", received))
+}
+
+// the reply credits the difference of the contract prefund balance, so deposit fees are not credited
+fn deposit_prefund_msg(
+    deps: DepsMut,
+    env: Env,
+    address: CanonicalAddr,
+    amount: Uint128,
+) -> Result<SubMsg, ContractError> {
+    let config = read_config(deps.storage)?;
+    let prefund_address = deps.api.addr_humanize(&config.prefund_address)?.into_string();
+    let prefund_balance = load_user_prefund_balance(
+        &deps.querier,
+        prefund_address.clone(),
+        env.contract.address.into_string(),
+    )?.available_funds;
+    store_pending_prefund(deps.storage, &PendingPrefund {
+        address,
+        ust_balance: Uint128::zero(),
+        prefund_balance,
+    })?;
+
+    Ok(SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: prefund_address,
+            msg: to_binary(&PrefundExecuteMsg::Deposit {})?,
+            funds: vec![Coin { denom: String::from("uusd"), amount }],
+        },
+        PREFUND_DEPOSIT_REPLY_ID,
+    ))
+}
+
+pub fn credit_prefund_deposit(
+    deps: DepsMut,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let pending_prefund = match read_pending_prefund(deps.storage)? {
+        Some(pending_prefund) => pending_prefund,
+        None => return Err(ContractError::UnknownReplyId {}),
+    };
+    remove_pending_prefund(deps.storage);
+
+    let config = read_config(deps.storage)?;
+    let prefund_balance = load_user_prefund_balance(
+        &deps.querier,
+        deps.api.addr_humanize(&config.prefund_address)?.into_string(),
+        env.contract.address.into_string(),
+    )?.available_funds;
+    let credited = prefund_balance.checked_sub(pending_prefund.prefund_balance).unwrap_or_default();
+    if credited.is_zero() {
+        return Err(ContractError::NoPrefundCredit {});
+    }
+    let prefund_credit = read_prefund_credit(deps.storage, &pending_prefund.address)? + credited;
+    store_prefund_credit(deps.storage, &pending_prefund.address, prefund_credit)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "credit_prefund")
+        .add_attribute("address", deps.api.addr_humanize(&pending_prefund.address)?.into_string())
+        .add_attribute("amount", credited)
+        .add_attribute("prefund_credit", prefund_credit))
+}
+
+pub fn withdraw_prefund_credit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let prefund_credit = read_prefund_credit(deps.storage, &sender)?;
+    if prefund_credit.is_zero() {
+        return Err(ContractError::NoPrefundCredit {});
+    }
+
+    // the credit of a participant pays for their allocation until the IDO is settled or cancelled
+    let config = read_config(deps.storage)?;
+    let state = read_state(deps.storage)?;
+    if read_participant(deps.storage, &sender)?.is_joined
+        && !state.is_settled
+        && !is_cancelled(&config, &state, env.block.time.seconds()) {
+        return Err(ContractError::PrefundCreditLocked {});
+    }
+
+    store_prefund_credit(deps.storage, &sender, Uint128::zero())?;
+    store_pending_prefund(deps.storage, &PendingPrefund {
+        address: sender,
+        ust_balance: query_balance(&deps.querier, env.contract.address, String::from("uusd"))?,
+        prefund_balance: Uint128::zero(),
+    })?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: deps.api.addr_humanize(&config.prefund_address)?.into_string(),
+                msg: to_binary(&PrefundExecuteMsg::WithdrawDeposit { amount: prefund_credit })?,
+                funds: vec![],
+            },
+            PREFUND_WITHDRAW_REPLY_ID,
+        ))
+        .add_attribute("action", "withdraw_prefund_credit")
+        .add_attribute("address", info.sender)
+        .add_attribute("amount", prefund_credit))
+}
+
+// withdrawal fees are taken by the prefund, the sender gets the uusd that arrived
+pub fn forward_withdrawn_prefund_credit(
+    deps: DepsMut,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let pending_prefund = match read_pending_prefund(deps.storage)? {
+        Some(pending_prefund) => pending_prefund,
+        None => return Err(ContractError::UnknownReplyId {}),
+    };
+    remove_pending_prefund(deps.storage);
+
+    let ust_balance = query_balance(&deps.querier, env.contract.address, String::from("uusd"))?;
+    let received = ust_balance.checked_sub(pending_prefund.ust_balance).unwrap_or_default();
+    if received.is_zero() {
+        return Err(ContractError::NoPrefundCredit {});
+    }
+    let address = deps.api.addr_humanize(&pending_prefund.address)?.into_string();
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: address.clone(),
+            amount: vec![Coin { denom: String::from("uusd"), amount: received }],
+        })
+        .add_attribute("action", "forward_prefund_credit")
+        .add_attribute("address", address)
+        .add_attribute("amount", received))
+}
@@ -3,6 +3,8 @@ use starterra_token::ido_prefund::{FunderInfoResponse};
 use starterra_token::kyc_vault::QueryMsg::{IsAcceptedVerified};
 use starterra_token::kyc_vault::IsAcceptedVerifiedResponse;
 use starterra_token::ido_prefund::QueryMsg::FunderInfo;
+use starterra_token::staking_gateway::BondAmountResponse;
+use starterra_token::staking_gateway::QueryMsg::BondAmount;
 
 // User balance from ido-prefund
 pub fn load_user_prefund_balance(
@@ -31,3 +33,16 @@ pub fn check_user_kyc_terms_verified(
             })?,
         }))
 }
+
+// User bond amount from staking-gateway
+pub fn load_user_bond_amount(
+    querier: &QuerierWrapper,
+    contract_addr: String,
+    user_address: String,
+) -> StdResult<BondAmountResponse> {
+    querier
+        .query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr,
+            msg: to_binary(&BondAmount { user: user_address })?,
+        }))
+}
@@ -1,8 +1,11 @@
-use cosmwasm_std::{StdResult, Deps, Env};
-use crate::state::{read_config, read_state, read_participant, read_participants};
-use starterra_token::ido::{ConfigResponse, StateResponse, StatusResponse, ParticipantResponse, ParticipantsResponse};
+use cosmwasm_std::{StdResult, Deps, Env, Decimal, Uint128};
+use crate::state::{read_config, read_state, read_participant, read_participants, read_ido_participant, read_lottery, read_lottery_result, read_snapshot, read_referral, read_referrer, read_referrer_leaderboard, read_phase_participants, read_prefund_credit};
+use starterra_token::ido::{ConfigResponse, StateResponse, StatusResponse, ParticipantResponse, ParticipantsResponse, ParticipantAccountsResponse, AllocationInfo, ParticipantAccountResponse, LotteryResponse, LotteryResultResponse, ParticipantSnapshot, ReferralsResponse, ReferrerResponse, ReferralLeaderboardResponse, EligibilityResponse, EligibilityCheck, EligibilityRule, PrefundCreditResponse};
 use std::borrow::Borrow;
-use starterra_token::common::{OrderBy, convert_raw_to_human};
+use starterra_token::common::OrderBy;
+use crate::tools::{is_cancelled, find_phase, time_to_next_phase, compute_unlocked_amount};
+use crate::querier::{load_user_prefund_balance, check_user_kyc_terms_verified, load_user_bond_amount};
+use starterra_token::vesting::common::{VestingAccountResponse, VestingInfo};
 
 pub fn query_config(
     deps: Deps,
@@ -18,15 +21,42 @@ pub fn query_config(
         paused: state.paused,
         snapshot_time: state.snapshot_time,
         minimum_prefund: state.minimum_prefund,
+        staking_gateway_address: deps.api.addr_humanize(&state.staking_gateway_address)?.into_string(),
+        tiers: state.tiers,
+        allocation_per_weight: state.allocation_per_weight,
+        soft_cap: state.soft_cap,
+        phases: state.phases,
+        hard_cap: state.hard_cap,
+        vesting: state.vesting,
+        leave_penalty: state.leave_penalty,
+        treasury_address: deps.api.addr_humanize(&state.treasury_address)?.into_string(),
+        terraswap_router: deps.api.addr_humanize(&state.terraswap_router)?.into_string(),
+        referral_share: state.referral_share,
     })
 }
 
 pub fn query_ido_state(
     deps: Deps,
 ) -> StdResult<StateResponse> {
+    let config = read_config(deps.storage)?;
     let state = read_state(deps.storage)?;
+    let raised = state.total_committed.checked_sub(state.lottery_released).unwrap_or_default();
+    let (remaining, percent_filled) = if config.hard_cap.is_zero() {
+        (None, None)
+    } else {
+        (
+            Some(config.hard_cap.checked_sub(raised).unwrap_or_default()),
+            Some(Decimal::from_ratio(raised * Uint128::from(100u128), config.hard_cap)),
+        )
+    };
     Ok(StateResponse {
         number_of_participants: state.number_of_participants,
+        total_allocation: state.total_allocation,
+        raised,
+        remaining,
+        percent_filled,
+        is_snapshot_taken: state.is_snapshot_taken,
+        is_settled: state.is_settled,
     })
 }
 
@@ -41,9 +71,7 @@ pub fn query_participant(
             Ok(res)
         }
         _ => {
-            Ok(ParticipantResponse {
-                is_joined: false,
-            })
+            Ok(ParticipantResponse::default())
         }
     }
 }
@@ -54,11 +82,15 @@ pub fn query_ido_status(
     block_time: Option<u64>,
 ) -> StdResult<StatusResponse> {
     let state = read_config(deps.storage)?;
-    let is_closed = state.end_date < block_time.unwrap_or(env.block.time.seconds());
+    let block_time = block_time.unwrap_or(env.block.time.seconds());
+    let is_closed = state.end_date < block_time;
     Ok(StatusResponse {
         is_closed,
         is_paused: state.paused,
         snapshot_time: state.snapshot_time,
+        is_cancelled: is_cancelled(&state, &read_state(deps.storage)?, block_time),
+        current_phase: find_phase(&state.phases, block_time).map(|(_, phase)| phase.name.clone()),
+        time_to_next_phase: time_to_next_phase(&state.phases, block_time),
     })
 }
 
@@ -78,6 +110,20 @@ pub fn query_participants(
     limit: Option<u32>,
     order_by: Option<OrderBy>,
 ) -> StdResult<ParticipantsResponse> {
+    let users = query_participant_accounts(deps, start_after, limit, order_by)?.participants
+        .into_iter()
+        .map(|participant| participant.address)
+        .collect();
+
+    Ok(ParticipantsResponse { users })
+}
+
+pub fn query_participant_accounts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<ParticipantAccountsResponse> {
     let users = if let Some(start_after) = start_after {
         read_participants(
             deps.storage,
@@ -89,5 +135,267 @@ pub fn query_participants(
         read_participants(deps.storage, None, limit, order_by)?
     };
 
-    return Ok(ParticipantsResponse { users: convert_raw_to_human(deps, &users)? });
+    let participants = users.into_iter()
+        .map(|(address, info)| -> StdResult<ParticipantAccountResponse> {
+            Ok(ParticipantAccountResponse {
+                address: deps.api.addr_humanize(&address)?.into_string(),
+                info,
+            })
+        })
+        .collect::<StdResult<Vec<ParticipantAccountResponse>>>()?;
+
+    Ok(ParticipantAccountsResponse { participants })
+}
+
+pub fn query_allocation(
+    deps: Deps,
+    address: String,
+) -> StdResult<AllocationInfo> {
+    let user_raw = deps.api.addr_canonicalize(&address)?;
+    let participant_info = read_ido_participant(deps.storage, &user_raw)?;
+
+    Ok(AllocationInfo {
+        address,
+        allocation: participant_info.allocation,
+        ido_token_amount: participant_info.ido_token_amount,
+        claimed_amount: participant_info.claimed_amount,
+    })
+}
+
+pub fn query_claimable_at(
+    deps: Deps,
+    env: Env,
+    address: String,
+    block_time: Option<u64>,
+) -> StdResult<VestingAccountResponse> {
+    let config = read_config(deps.storage)?;
+    let user_raw = deps.api.addr_canonicalize(&address)?;
+    let participant_info = read_ido_participant(deps.storage, &user_raw)?;
+
+    let possible_claim = if read_state(deps.storage)?.is_settled {
+        let block_time = block_time.unwrap_or(env.block.time.seconds());
+        compute_unlocked_amount(&config.vesting, participant_info.ido_token_amount, block_time)
+            .checked_sub(participant_info.claimed_amount)
+            .unwrap_or_default()
+    } else {
+        Uint128::zero()
+    };
+
+    Ok(VestingAccountResponse {
+        address,
+        info: VestingInfo {
+            amount: participant_info.ido_token_amount,
+            already_claimed: participant_info.claimed_amount,
+        },
+        possible_claim,
+    })
+}
+
+pub fn query_snapshot(
+    deps: Deps,
+    address: String,
+) -> StdResult<Option<ParticipantSnapshot>> {
+    let user_raw = deps.api.addr_canonicalize(&address)?;
+    read_snapshot(deps.storage, &user_raw)
+}
+
+pub fn query_lottery(
+    deps: Deps,
+) -> StdResult<LotteryResponse> {
+    let state = read_state(deps.storage)?;
+    match read_lottery(deps.storage)? {
+        Some(lottery) => Ok(LotteryResponse {
+            seed_hash: Some(lottery.seed_hash),
+            seed: lottery.seed,
+            entrants: state.lottery_entrants,
+            winning_tickets: lottery.winning_tickets,
+            winners: lottery.winning_tickets - lottery.remaining_tickets,
+            is_drawn: lottery.is_drawn,
+        }),
+        None => Ok(LotteryResponse {
+            seed_hash: None,
+            seed: None,
+            entrants: state.lottery_entrants,
+            winning_tickets: 0,
+            winners: 0,
+            is_drawn: false,
+        }),
+    }
+}
+
+pub fn query_lottery_result(
+    deps: Deps,
+    address: String,
+) -> StdResult<LotteryResultResponse> {
+    let user_raw = deps.api.addr_canonicalize(&address)?;
+    let participant = read_participant(deps.storage, &user_raw)?;
+    let result = read_lottery_result(deps.storage, &user_raw)?;
+    let is_drawn = result.is_some() || matches!(read_lottery(deps.storage)?, Some(lottery) if lottery.is_drawn);
+
+    Ok(LotteryResultResponse {
+        address,
+        is_entrant: participant.lottery,
+        is_drawn,
+        is_winner: result.unwrap_or(false),
+    })
+}
+
+pub fn query_referrals(
+    deps: Deps,
+    address: String,
+) -> StdResult<ReferralsResponse> {
+    let user_raw = deps.api.addr_canonicalize(&address)?;
+    let referrer = match read_referral(deps.storage, &user_raw)? {
+        Some(referrer) => Some(deps.api.addr_humanize(&referrer)?.into_string()),
+        None => None,
+    };
+    let referrer_info = read_referrer(deps.storage, &user_raw)?;
+
+    Ok(ReferralsResponse {
+        address,
+        referrer,
+        referees: referrer_info.referees,
+        reward: referrer_info.reward,
+    })
+}
+
+pub fn query_referral_leaderboard(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<ReferralLeaderboardResponse> {
+    let start_after = match start_after {
+        Some(start_after) => Some(deps.api.addr_canonicalize(&start_after)?),
+        None => None,
+    };
+    let referrers = read_referrer_leaderboard(deps.storage, start_after, limit, order_by)?
+        .into_iter()
+        .map(|(address, referrer)| -> StdResult<ReferrerResponse> {
+            Ok(ReferrerResponse {
+                address: deps.api.addr_humanize(&address)?.into_string(),
+                referees: referrer.referees,
+                reward: referrer.reward,
+            })
+        })
+        .collect::<StdResult<Vec<ReferrerResponse>>>()?;
+
+    Ok(ReferralLeaderboardResponse { referrers })
+}
+
+pub fn query_eligibility(
+    deps: Deps,
+    env: Env,
+    address: String,
+    block_time: Option<u64>,
+) -> StdResult<EligibilityResponse> {
+    let config = read_config(deps.storage)?;
+    let state = read_state(deps.storage)?;
+    let block_time = block_time.unwrap_or(env.block.time.seconds());
+    let user_raw = deps.api.addr_canonicalize(&address)?;
+
+    let mut checks = vec![
+        check(EligibilityRule::NotJoined, !read_participant(deps.storage, &user_raw)?.is_joined),
+        check(EligibilityRule::NotPaused, !config.paused),
+        check(EligibilityRule::NotClosed, block_time <= config.end_date),
+        check(EligibilityRule::NotCancelled, !is_cancelled(&config, &state, block_time)),
+        check(EligibilityRule::BeforeSnapshot, !matches!(config.snapshot_time, Some(snapshot_time) if snapshot_time <= block_time)),
+    ];
+
+    // without phases the sale is open for everyone until end_date
+    let mut minimum_prefund = config.minimum_prefund;
+    let mut stakers_only = false;
+    if !config.phases.is_empty() {
+        match find_phase(&config.phases, block_time) {
+            Some((index, phase)) => {
+                checks.push(check(EligibilityRule::ActivePhase, true));
+                let phase_participants = read_phase_participants(deps.storage, index)?;
+                checks.push(EligibilityCheck {
+                    rule: EligibilityRule::PhaseCapacity,
+                    passed: !matches!(phase.max_participants, Some(max_participants) if phase_participants >= max_participants),
+                    required: phase.max_participants.map(Uint128::from),
+                    actual: Some(Uint128::from(phase_participants)),
+                });
+                minimum_prefund = phase.minimum_prefund.unwrap_or(config.minimum_prefund);
+                stakers_only = phase.stakers_only;
+            }
+            None => checks.push(check(EligibilityRule::ActivePhase, false)),
+        }
+    }
+
+    let available_funds = load_user_prefund_balance(
+        &deps.querier,
+        deps.api.addr_humanize(&config.prefund_address)?.into_string(),
+        address.clone(),
+    )?.available_funds + read_prefund_credit(deps.storage, &user_raw)?;
+    checks.push(EligibilityCheck {
+        rule: EligibilityRule::Prefund,
+        passed: available_funds >= minimum_prefund,
+        required: Some(minimum_prefund),
+        actual: Some(available_funds),
+    });
+
+    let kyc_terms_info = check_user_kyc_terms_verified(
+        &deps.querier,
+        deps.api.addr_humanize(&config.kyc_terms_vault_address)?.into_string(),
+        address.clone(),
+    )?;
+    checks.push(check(EligibilityRule::Kyc, kyc_terms_info.is_verified));
+    checks.push(check(EligibilityRule::TermsOfUse, kyc_terms_info.is_accepted));
+
+    // the lowest tier is the minimum bond, stakers only phases just need any bond
+    if !config.tiers.is_empty() || stakers_only {
+        let bond_info = load_user_bond_amount(
+            &deps.querier,
+            deps.api.addr_humanize(&config.staking_gateway_address)?.into_string(),
+            address.clone(),
+        )?;
+        let lowest_tier_bond = config.tiers.first().map(|tier| tier.min_bond).unwrap_or_default();
+        let required_bond = if stakers_only {
+            std::cmp::max(lowest_tier_bond, Uint128::from(1u128))
+        } else {
+            lowest_tier_bond
+        };
+        checks.push(EligibilityCheck {
+            rule: EligibilityRule::Staking,
+            passed: bond_info.bond_amount >= required_bond,
+            required: Some(required_bond),
+            actual: Some(bond_info.bond_amount),
+        });
+    }
+
+    if !config.hard_cap.is_zero() {
+        checks.push(EligibilityCheck {
+            rule: EligibilityRule::HardCap,
+            passed: state.total_committed < config.hard_cap,
+            required: Some(config.hard_cap),
+            actual: Some(state.total_committed),
+        });
+    }
+
+    Ok(EligibilityResponse {
+        address,
+        is_eligible: checks.iter().all(|check| check.passed),
+        checks,
+    })
+}
+
+pub fn query_prefund_credit(
+    deps: Deps,
+    address: String,
+) -> StdResult<PrefundCreditResponse> {
+    let amount = read_prefund_credit(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    Ok(PrefundCreditResponse {
+        address,
+        amount,
+    })
+}
+
+fn check(rule: EligibilityRule, passed: bool) -> EligibilityCheck {
+    EligibilityCheck {
+        rule,
+        passed,
+        required: None,
+        actual: None,
+    }
 }
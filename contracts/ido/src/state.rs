@@ -1,16 +1,25 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{StdResult, Storage, Uint128, CanonicalAddr};
-use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
-use starterra_token::ido::{ParticipantInfoResponse, ParticipantResponse};
+use cosmwasm_std::{StdResult, Storage, Uint128, CanonicalAddr, Decimal};
+use cosmwasm_storage::{singleton, singleton_read, Singleton, Bucket, ReadonlyBucket};
+use starterra_token::ido::{ParticipantInfoResponse, ParticipantResponse, Tier, Phase, VestingSchedule, ParticipantSnapshot};
 use starterra_token::common::OrderBy;
 
 static KEY_CONFIG: &[u8] = b"config";
 static KEY_STATE: &[u8] = b"state";
+static KEY_LOTTERY: &[u8] = b"lottery";
+static KEY_PENDING_PREFUND: &[u8] = b"pending_prefund";
 
 static PREFIX_KEY_IDO_PARTICIPANT: &[u8] = b"ido_participant";
 static PREFIX_KEY_PARTICIPANT: &[u8] = b"participant";
+static PREFIX_KEY_LOTTERY_RESULT: &[u8] = b"lottery_result";
+static PREFIX_KEY_PHASE_PARTICIPANTS: &[u8] = b"phase_participants";
+static PREFIX_KEY_SNAPSHOT: &[u8] = b"snapshot";
+static PREFIX_KEY_REFERRAL: &[u8] = b"referral";
+static PREFIX_KEY_REFERRER: &[u8] = b"referrer";
+static PREFIX_KEY_REFERRER_RANK: &[u8] = b"referrer_rank";
+static PREFIX_KEY_PREFUND_CREDIT: &[u8] = b"prefund_credit";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -24,6 +33,17 @@ pub struct Config {
     pub paused: bool,
     pub snapshot_time: Option<u64>,
     pub minimum_prefund: Uint128,
+    pub staking_gateway_address: CanonicalAddr,
+    pub tiers: Vec<Tier>,
+    pub allocation_per_weight: Uint128,
+    pub soft_cap: Uint128,
+    pub phases: Vec<Phase>,
+    pub hard_cap: Uint128,
+    pub vesting: Option<VestingSchedule>,
+    pub leave_penalty: Uint128,
+    pub treasury_address: CanonicalAddr,
+    pub terraswap_router: CanonicalAddr,
+    pub referral_share: Decimal,
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -34,9 +54,54 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     singleton_read(storage, KEY_CONFIG).load()
 }
 
+// the config stored before tiers, caps, phases, vesting and referrals existed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub owner: CanonicalAddr,
+    pub pending_owner: Option<CanonicalAddr>,
+    pub prefund_address: CanonicalAddr,
+    pub kyc_terms_vault_address: CanonicalAddr,
+    pub ido_token: CanonicalAddr,
+    pub ido_token_price: Uint128,
+    pub end_date: u64,
+    pub paused: bool,
+    pub snapshot_time: Option<u64>,
+    pub minimum_prefund: Uint128,
+}
+
+pub fn read_legacy_config(storage: &dyn Storage) -> Option<LegacyConfig> {
+    // a current config parses as a legacy one as well
+    if read_config(storage).is_ok() {
+        return None;
+    }
+
+    singleton_read::<LegacyConfig>(storage, KEY_CONFIG).load().ok()
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub number_of_participants: u64,
+    // defaults keep the state stored before settlement readable
+    #[serde(default)]
+    pub total_allocation: Uint128,
+    #[serde(default)]
+    pub settlement_cursor: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub is_settled: bool,
+    #[serde(default)]
+    pub lottery_entrants: u64,
+    // committed at join time, decides the soft cap
+    #[serde(default)]
+    pub total_committed: Uint128,
+    // commitments of lottery losers, no longer part of the raised amount
+    #[serde(default)]
+    pub lottery_released: Uint128,
+    #[serde(default)]
+    pub is_cancelled: bool,
+    #[serde(default)]
+    pub snapshot_participants: u64,
+    #[serde(default)]
+    pub is_snapshot_taken: bool,
 }
 
 pub fn store_state(storage: &mut dyn Storage, state: &State) -> StdResult<()> {
@@ -47,6 +112,196 @@ pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
     singleton_read(storage, KEY_STATE).load()
 }
 
+// Kept between a prefund submessage and its reply, the received uusd and the credited
+// deposit are the differences of the contract uusd balance and of its prefund available_funds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPrefund {
+    pub address: CanonicalAddr,
+    pub ust_balance: Uint128,
+    pub prefund_balance: Uint128,
+}
+
+pub fn store_pending_prefund(storage: &mut dyn Storage, pending_prefund: &PendingPrefund) -> StdResult<()> {
+    singleton(storage, KEY_PENDING_PREFUND).save(pending_prefund)
+}
+
+pub fn read_pending_prefund(storage: &dyn Storage) -> StdResult<Option<PendingPrefund>> {
+    singleton_read(storage, KEY_PENDING_PREFUND).may_load()
+}
+
+pub fn remove_pending_prefund(storage: &mut dyn Storage) {
+    Singleton::<PendingPrefund>::new(storage, KEY_PENDING_PREFUND).remove();
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Lottery {
+    pub seed_hash: String,
+    pub seed: Option<String>,
+    pub committed_at: u64,
+    pub winning_tickets: u64,
+    pub remaining_entrants: u64,
+    pub remaining_tickets: u64,
+    pub cursor: Option<CanonicalAddr>,
+    pub is_drawn: bool,
+}
+
+pub fn store_lottery(storage: &mut dyn Storage, lottery: &Lottery) -> StdResult<()> {
+    singleton(storage, KEY_LOTTERY).save(lottery)
+}
+
+pub fn read_lottery(storage: &dyn Storage) -> StdResult<Option<Lottery>> {
+    singleton_read(storage, KEY_LOTTERY).may_load()
+}
+
+pub fn store_lottery_result(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+    is_winner: bool,
+) -> StdResult<()> {
+    Bucket::<bool>::new(storage, PREFIX_KEY_LOTTERY_RESULT).save(address, &is_winner)
+}
+
+pub fn read_lottery_result(
+    storage: &dyn Storage,
+    address: &CanonicalAddr,
+) -> StdResult<Option<bool>> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_LOTTERY_RESULT).may_load(address)
+}
+
+pub fn store_snapshot(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+    snapshot: &ParticipantSnapshot,
+) -> StdResult<()> {
+    Bucket::<ParticipantSnapshot>::new(storage, PREFIX_KEY_SNAPSHOT).save(address, snapshot)
+}
+
+pub fn read_snapshot(
+    storage: &dyn Storage,
+    address: &CanonicalAddr,
+) -> StdResult<Option<ParticipantSnapshot>> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_SNAPSHOT).may_load(address)
+}
+
+// referee -> referrer
+pub fn store_referral(
+    storage: &mut dyn Storage,
+    referee: &CanonicalAddr,
+    referrer: &CanonicalAddr,
+) -> StdResult<()> {
+    Bucket::<CanonicalAddr>::new(storage, PREFIX_KEY_REFERRAL).save(referee, referrer)
+}
+
+pub fn remove_referral(
+    storage: &mut dyn Storage,
+    referee: &CanonicalAddr,
+) {
+    Bucket::<CanonicalAddr>::new(storage, PREFIX_KEY_REFERRAL).remove(referee)
+}
+
+pub fn read_referral(
+    storage: &dyn Storage,
+    referee: &CanonicalAddr,
+) -> StdResult<Option<CanonicalAddr>> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_REFERRAL).may_load(referee)
+}
+
+// uusd the IDO deposited to the prefund on behalf of the address
+pub fn store_prefund_credit(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+    amount: Uint128,
+) -> StdResult<()> {
+    Bucket::<Uint128>::new(storage, PREFIX_KEY_PREFUND_CREDIT).save(address, &amount)
+}
+
+pub fn read_prefund_credit(
+    storage: &dyn Storage,
+    address: &CanonicalAddr,
+) -> StdResult<Uint128> {
+    Ok(ReadonlyBucket::new(storage, PREFIX_KEY_PREFUND_CREDIT)
+        .may_load(address)?
+        .unwrap_or_default())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Referrer {
+    pub referees: u64,
+    pub reward: Uint128,
+}
+
+// Referrers are also indexed by reward + address, so the leaderboard is a plain range
+pub fn store_referrer(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+    referrer: &Referrer,
+) -> StdResult<()> {
+    let previous = read_referrer(storage, address)?;
+    let mut rank: Bucket<bool> = Bucket::new(storage, PREFIX_KEY_REFERRER_RANK);
+    rank.remove(&rank_key(previous.reward, address));
+    rank.save(&rank_key(referrer.reward, address), &true)?;
+
+    Bucket::<Referrer>::new(storage, PREFIX_KEY_REFERRER).save(address, referrer)
+}
+
+pub fn read_referrer(
+    storage: &dyn Storage,
+    address: &CanonicalAddr,
+) -> StdResult<Referrer> {
+    Ok(ReadonlyBucket::new(storage, PREFIX_KEY_REFERRER)
+        .may_load(address)?
+        .unwrap_or_default())
+}
+
+pub fn read_referrer_leaderboard(
+    storage: &dyn Storage,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<Vec<(CanonicalAddr, Referrer)>> {
+    let limit = limit.unwrap_or(DEFAULT_LEADERBOARD_LIMIT).min(MAX_LEADERBOARD_LIMIT) as usize;
+    let start_after = match start_after {
+        Some(address) => Some(rank_key(read_referrer(storage, &address)?.reward, &address)),
+        None => None,
+    };
+    let (start, end, order_by) = match order_by {
+        Some(OrderBy::Asc) => (start_after.map(|mut key| { key.push(1); key }), None, OrderBy::Asc),
+        _ => (None, start_after, OrderBy::Desc),
+    };
+
+    let rank: ReadonlyBucket<bool> = ReadonlyBucket::new(storage, PREFIX_KEY_REFERRER_RANK);
+    rank.range(start.as_deref(), end.as_deref(), order_by.into())
+        .take(limit)
+        .map(|item| {
+            let (k, _) = item?;
+            let address = CanonicalAddr::from(&k[16..]);
+            let referrer = read_referrer(storage, &address)?;
+            Ok((address, referrer))
+        })
+        .collect()
+}
+
+fn rank_key(reward: Uint128, address: &CanonicalAddr) -> Vec<u8> {
+    [&reward.u128().to_be_bytes()[..], address.as_slice()].concat()
+}
+
+pub fn store_phase_participants(
+    storage: &mut dyn Storage,
+    phase: u32,
+    number_of_participants: u64,
+) -> StdResult<()> {
+    Bucket::<u64>::new(storage, PREFIX_KEY_PHASE_PARTICIPANTS).save(&phase.to_be_bytes(), &number_of_participants)
+}
+
+pub fn read_phase_participants(
+    storage: &dyn Storage,
+    phase: u32,
+) -> StdResult<u64> {
+    Ok(ReadonlyBucket::new(storage, PREFIX_KEY_PHASE_PARTICIPANTS)
+        .may_load(&phase.to_be_bytes())?
+        .unwrap_or(0))
+}
+
 pub fn store_ido_participant(
     storage: &mut dyn Storage,
     address: &CanonicalAddr,
@@ -67,7 +322,15 @@ pub fn read_ido_participant(
     storage: &dyn Storage,
     address: &CanonicalAddr,
 ) -> StdResult<ParticipantInfoResponse> {
-    Ok(ReadonlyBucket::new(storage, PREFIX_KEY_IDO_PARTICIPANT).load(address)?)
+    match ReadonlyBucket::new(storage, PREFIX_KEY_IDO_PARTICIPANT).may_load(address)? {
+        Some(found) => Ok(found),
+        None => Ok(ParticipantInfoResponse {
+            allocation: Uint128::zero(),
+            is_joined: false,
+            ido_token_amount: Uint128::zero(),
+            claimed_amount: Uint128::zero(),
+        }),
+    }
 }
 
 pub fn store_participant(
@@ -79,26 +342,33 @@ pub fn store_participant(
         .save(address, participant_info)?)
 }
 
+pub fn remove_participant(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+) {
+    Bucket::<ParticipantResponse>::new(storage, PREFIX_KEY_PARTICIPANT).remove(address)
+}
+
 pub fn read_participant(
     storage: &dyn Storage,
     address: &CanonicalAddr,
 ) -> StdResult<ParticipantResponse> {
     match ReadonlyBucket::new(storage, PREFIX_KEY_PARTICIPANT).may_load(address)? {
         Some(found) => Ok(found),
-        None => Ok(ParticipantResponse {
-            is_joined: false,
-        }),
+        None => Ok(ParticipantResponse::default()),
     }
 }
 
 
 const DEFAULT_LIMIT: u32 = 1024;
+const DEFAULT_LEADERBOARD_LIMIT: u32 = 10;
+const MAX_LEADERBOARD_LIMIT: u32 = 30;
 pub fn read_participants(
     storage: &dyn Storage,
     start_after: Option<CanonicalAddr>,
     limit: Option<u32>,
     order_by: Option<OrderBy>,
-) -> StdResult<Vec<CanonicalAddr>> {
+) -> StdResult<Vec<(CanonicalAddr, ParticipantResponse)>> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
     let (start, end, order_by) = match order_by {
         Some(OrderBy::Asc) => (calc_range_start_addr(start_after), None, OrderBy::Asc),
@@ -112,8 +382,8 @@ pub fn read_participants(
         .range(start.as_deref(), end.as_deref(), order_by.into())
         .take(limit)
         .map(|item| {
-            let (k, _) = item?;
-            Ok(CanonicalAddr::from(k))
+            let (k, v) = item?;
+            Ok((CanonicalAddr::from(k), v))
         })
         .collect();
 }
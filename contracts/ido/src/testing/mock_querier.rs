@@ -6,8 +6,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use starterra_token::ido_prefund::FunderInfoResponse;
 use starterra_token::kyc_vault::{IsAcceptedVerifiedResponse};
+use starterra_token::staking_gateway::BondAmountResponse;
 use std::ops::Deref;
-use crate::testing::mock_querier::QueryMsgMock::{FunderInfo, IsAcceptedVerified};
+use crate::testing::mock_querier::QueryMsgMock::{FunderInfo, IsAcceptedVerified, BondAmount};
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
 /// this uses our CustomQuerier.
@@ -36,6 +37,7 @@ pub struct TokenQuerier {
     // this lets us iterate over all pairs that match the first string
     account_info: HashMap<String, HashMap<String, FunderInfoResponse>>,
     kyc_info: HashMap<String, HashMap<String, IsAcceptedVerifiedResponse>>,
+    bond_info: HashMap<String, HashMap<String, Uint128>>,
 }
 
 impl TokenQuerier {
@@ -46,6 +48,7 @@ impl TokenQuerier {
         TokenQuerier {
             account_info: account_info_to_map(account_info),
             kyc_info: account_info_to_terms_map(kyc_info),
+            bond_info: HashMap::new(),
         }
     }
 }
@@ -76,6 +79,8 @@ pub(crate) fn account_info_to_terms_map(
                 address: String::from(addr),
                 is_accepted: info.0,
                 is_verified: info.1,
+                expires_at: None,
+                is_blocked: false,
             });
         }
 
@@ -109,6 +114,9 @@ pub enum QueryMsgMock {
     FunderInfo {
         address: String,
     },
+    BondAmount {
+        user: String,
+    },
     Config {},
 }
 
@@ -132,11 +140,24 @@ impl WasmMockQuerier {
                             address: address.clone(),
                             is_accepted: false,
                             is_verified: false,
+                            expires_at: None,
+                            is_blocked: false,
                         };
                         let map_for_contract = self.token_querier.kyc_info.get(contract_addr.clone().as_str()).unwrap();
                         let resp = map_for_contract.get(address.as_str()).unwrap_or_else(|| &is_accepted_response);
                         SystemResult::Ok(ContractResult::from(to_binary(&resp)))
                     },
+                    BondAmount { user } => {
+                        let bond_amount = self.token_querier.bond_info.get(contract_addr.clone().as_str())
+                            .and_then(|map_for_contract| map_for_contract.get(user.as_str()))
+                            .cloned()
+                            .unwrap_or_default();
+                        SystemResult::Ok(ContractResult::from(to_binary(&BondAmountResponse {
+                            user,
+                            contract: None,
+                            bond_amount,
+                        })))
+                    },
 
                     _ => self.base.handle_query(request),
                 }
@@ -162,4 +183,19 @@ impl WasmMockQuerier {
     ) {
         self.token_querier = TokenQuerier::new(account_statuses, kyc_terms_statuses);
     }
+
+    pub fn with_bond_amounts(
+        &mut self,
+        bond_amounts: Vec<(String, Vec<(String, Uint128)>)>,
+    ) {
+        let mut bond_info: HashMap<String, HashMap<String, Uint128>> = HashMap::new();
+        for (contract_addr, amounts) in bond_amounts.into_iter() {
+            bond_info.insert(contract_addr, amounts.into_iter().collect());
+        }
+        self.token_querier.bond_info = bond_info;
+    }
+
+    pub fn with_balance(&mut self, address: &str, balance: Vec<Coin>) {
+        self.base.update_balance(address, balance);
+    }
 }
@@ -1,13 +1,19 @@
-use cosmwasm_std::{attr, from_binary, Timestamp, Uint128};
-use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{attr, from_binary, to_binary, Timestamp, Uint128, CosmosMsg, WasmMsg, Decimal, BankMsg, coins, SubMsg, Reply, ContractResult, SubMsgExecutionResponse, Api, Binary, Storage, OwnedDeps};
+use cosmwasm_storage::to_length_prefixed;
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use terraswap::asset::AssetInfo;
+use terraswap::router::{SwapOperation, ExecuteMsg as RouterExecuteMsg, Cw20HookMsg as RouterCw20HookMsg};
 
-use starterra_token::ido::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, StateResponse, StatusResponse, ParticipantResponse, ParticipantsResponse};
-use starterra_token::ido_prefund::FunderInfoResponse;
+use starterra_token::ido::{Cw20HookMsg, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, StateResponse, StatusResponse, ParticipantResponse, ParticipantsResponse, ParticipantAccountsResponse, AllocationInfo, ParticipantAccountResponse, Tier, LotteryResponse, LotteryResultResponse, Phase, VestingSchedule, ParticipantSnapshot, ReferralsResponse, ReferrerResponse, ReferralLeaderboardResponse, EligibilityResponse, EligibilityCheck, EligibilityRule, PrefundCreditResponse};
+use starterra_token::ido_prefund::{FunderInfoResponse, FunderRequest, ExecuteMsg as PrefundExecuteMsg};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query, reply};
 use crate::errors::ContractError;
-use crate::testing::mock_querier::mock_dependencies;
+use crate::testing::mock_querier::{mock_dependencies, WasmMockQuerier};
+use crate::tools::{sha256_hex, lottery_ticket};
 use starterra_token::common::OrderBy;
+use starterra_token::vesting::common::{VestingAccountResponse, VestingInfo};
 
 #[test]
 fn proper_instantiate() {
@@ -22,6 +28,17 @@ fn proper_instantiate() {
         end_date: 100000u64,
         paused: false,
         minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
     };
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(100);
@@ -39,6 +56,17 @@ fn proper_instantiate() {
             paused: false,
             snapshot_time: None,
             minimum_prefund: Uint128::from(500u128),
+            staking_gateway_address: String::from("staking_gateway_address"),
+            tiers: vec![],
+            allocation_per_weight: Uint128::zero(),
+            soft_cap: Uint128::zero(),
+            phases: vec![],
+            hard_cap: Uint128::zero(),
+            vesting: None,
+            leave_penalty: Uint128::zero(),
+            treasury_address: String::from("treasury_address"),
+            terraswap_router: String::from("router0000"),
+            referral_share: Decimal::zero(),
         }
     );
 }
@@ -58,6 +86,17 @@ fn instantiate_with_end_date_in_the_past() {
         end_date: 99u64,
         paused: false,
         minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
     };
     let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
     assert_eq!(
@@ -79,6 +118,17 @@ fn update_config() {
         end_date: 100000u64,
         paused: false,
         minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
     };
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(100);
@@ -95,6 +145,17 @@ fn update_config() {
         paused: Some(true),
         snapshot_time: Some(100u64),
         minimum_prefund: Some(Uint128::from(321321u128)),
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
     };
     let info = mock_info("owner", &vec![]);
     let mut env = mock_env();
@@ -118,6 +179,17 @@ fn update_config() {
             paused: true,
             snapshot_time: Some(100u64),
             minimum_prefund: Uint128::from(321321u128),
+            staking_gateway_address: String::from("staking_gateway_address"),
+            tiers: vec![],
+            allocation_per_weight: Uint128::zero(),
+            soft_cap: Uint128::zero(),
+            phases: vec![],
+            hard_cap: Uint128::zero(),
+            vesting: None,
+            leave_penalty: Uint128::zero(),
+            treasury_address: String::from("treasury_address"),
+            terraswap_router: String::from("router0000"),
+            referral_share: Decimal::zero(),
         }
     );
 
@@ -127,6 +199,9 @@ fn update_config() {
             is_paused: true,
             is_closed: true,
             snapshot_time: Some(100u64),
+            is_cancelled: false,
+            current_phase: None,
+            time_to_next_phase: None,
         }
     );
 
@@ -140,6 +215,17 @@ fn update_config() {
         paused: Some(false),
         snapshot_time: None,
         minimum_prefund: Some(Uint128::from(321321u128)),
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
     };
     let info = mock_info("owner", &vec![]);
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -159,6 +245,17 @@ fn update_config() {
         paused: Some(true),
         snapshot_time: Some(100u64),
         minimum_prefund: Some(Uint128::from(321321u128)),
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
     };
     let info = mock_info("new_owner", &vec![]);
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -181,6 +278,17 @@ fn accept_ownership() {
         end_date: 100000u64,
         paused: false,
         minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
     };
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(100);
@@ -207,6 +315,17 @@ fn accept_ownership() {
         paused: None,
         snapshot_time: None,
         minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
     };
     let info = mock_info("owner", &vec![]);
     let env = mock_env();
@@ -269,6 +388,17 @@ fn join_ido() {
         end_date: 100000u64,
         paused: false,
         minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
     };
 
     let info = mock_info("addr0000", &vec![]);
@@ -277,7 +407,7 @@ fn join_ido() {
     let _res = instantiate(deps.as_mut(), env, info.clone(), msg).unwrap();
 
     // not enough prefund
-    let msg = ExecuteMsg::JoinIdo {};
+    let msg = ExecuteMsg::JoinIdo { referrer: None };
     let info = mock_info("ido_address_2", &[]);
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(100);
@@ -289,7 +419,7 @@ fn join_ido() {
 
 
     // join ido success
-    let msg = ExecuteMsg::JoinIdo {};
+    let msg = ExecuteMsg::JoinIdo { referrer: None };
     let info2 = mock_info("ido_address_1", &[]);
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(100);
@@ -317,6 +447,11 @@ fn join_ido() {
             .unwrap(),
         ParticipantResponse {
             is_joined: true,
+            tier: None,
+            weight: 0,
+            lottery: false,
+            committed: Uint128::from(500u128),
+            phase: None,
         }
     );
 
@@ -332,9 +467,7 @@ fn join_ido() {
                 .unwrap()
         )
             .unwrap(),
-        ParticipantResponse {
-            is_joined: false,
-        }
+        ParticipantResponse::default()
     );
 
     assert_eq!(
@@ -349,6 +482,12 @@ fn join_ido() {
             .unwrap(),
         StateResponse {
             number_of_participants: 1,
+            total_allocation: Uint128::zero(),
+            raised: Uint128::from(500u128),
+            remaining: None,
+            percent_filled: None,
+            is_snapshot_taken: false,
+            is_settled: false,
         }
     );
 }
@@ -388,6 +527,17 @@ fn cant_join_ido_after_end_time() {
         end_date: 100000u64,
         paused: true,
         minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
     };
 
     let info = mock_info("addr0000", &vec![]);
@@ -395,7 +545,7 @@ fn cant_join_ido_after_end_time() {
     env.block.time = Timestamp::from_seconds(100);
     let _res = instantiate(deps.as_mut(), env, info.clone(), msg).unwrap();
 
-    let msg = ExecuteMsg::JoinIdo {};
+    let msg = ExecuteMsg::JoinIdo { referrer: None };
     let info = mock_info("ido_address_1", &[]);
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
 
@@ -410,6 +560,9 @@ fn cant_join_ido_after_end_time() {
             is_paused: true,
             is_closed: false,
             snapshot_time: None,
+            is_cancelled: false,
+            current_phase: None,
+            time_to_next_phase: None,
         }
     );
 
@@ -423,13 +576,24 @@ fn cant_join_ido_after_end_time() {
         paused: Some(false),
         snapshot_time: None,
         minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
     };
     let info = mock_info("owner", &vec![]);
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(100);
     let _res = execute(deps.as_mut(), env, info.clone(), msg).unwrap();
 
-    let msg = ExecuteMsg::JoinIdo {};
+    let msg = ExecuteMsg::JoinIdo { referrer: None };
     let info = mock_info("ido_address_1", &[]);
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(100001u64);
@@ -446,6 +610,9 @@ fn cant_join_ido_after_end_time() {
             is_paused: false,
             is_closed: true,
             snapshot_time: None,
+            is_cancelled: false,
+            current_phase: None,
+            time_to_next_phase: None,
         }
     );
 }
@@ -487,20 +654,31 @@ fn cant_join_ido_second_time() {
         end_date: 100000u64,
         paused: false,
         minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
     };
 
     let info = mock_info("addr0000", &vec![]);
     let _res = instantiate(deps.as_mut(), env, info.clone(), msg).unwrap();
 
     // join ido
-    let msg = ExecuteMsg::JoinIdo {};
+    let msg = ExecuteMsg::JoinIdo { referrer: None };
     let info = mock_info("ido_address_1", &[]);
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(10000u64);
     let _res = execute(deps.as_mut(), env, info.clone(), msg).unwrap();
 
     // join second time
-    let msg = ExecuteMsg::JoinIdo {};
+    let msg = ExecuteMsg::JoinIdo { referrer: None };
     let info = mock_info("ido_address_1", &[]);
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
 
@@ -525,6 +703,17 @@ fn query_snapshot() {
         end_date: 1000000u64,
         paused: false,
         minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
     };
 
     let info = mock_info("addr0000", &vec![]);
@@ -545,6 +734,17 @@ fn query_snapshot() {
         paused: None,
         snapshot_time: Some(2000000u64),
         minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
     };
 
     let info = mock_info("owner", &vec![]);
@@ -567,6 +767,17 @@ fn query_snapshot() {
         paused: None,
         snapshot_time: None,
         minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
     };
     let info = mock_info("owner", &vec![]);
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
@@ -586,6 +797,17 @@ fn query_snapshot() {
         paused: None,
         snapshot_time: Some(10u64),
         minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
     };
     let info = mock_info("owner", &vec![]);
     let mut env = mock_env();
@@ -654,6 +876,17 @@ fn query_participants() {
         end_date: 100000u64,
         paused: false,
         minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
     };
 
     let info = mock_info("addr0000", &vec![]);
@@ -662,19 +895,19 @@ fn query_participants() {
     let _res = instantiate(deps.as_mut(), env, info.clone(), msg).unwrap();
 
     // join ido success
-    let msg = ExecuteMsg::JoinIdo {};
+    let msg = ExecuteMsg::JoinIdo { referrer: None };
     let info2 = mock_info("ido_address_1", &[]);
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(100);
     execute(deps.as_mut(), env, info2.clone(), msg).unwrap();
 
-    let msg = ExecuteMsg::JoinIdo {};
+    let msg = ExecuteMsg::JoinIdo { referrer: None };
     let info2 = mock_info("ido_address_2", &[]);
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(100);
     execute(deps.as_mut(), env, info2.clone(), msg).unwrap();
 
-    let msg = ExecuteMsg::JoinIdo {};
+    let msg = ExecuteMsg::JoinIdo { referrer: None };
     let info2 = mock_info("ido_address_3", &[]);
     let mut env = mock_env();
     env.block.time = Timestamp::from_seconds(100);
@@ -709,5 +942,2560 @@ fn query_participants() {
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
     let participants: ParticipantsResponse = from_binary(&res).unwrap();
     assert_eq!(participants.users, vec!["ido_address_3"]);
+
+    //query for participant accounts
+    let msg = QueryMsg::ParticipantAccounts {
+        start_after: Some("ido_address_2".to_string()),
+        limit: Some(2),
+        order_by: Some(OrderBy::Asc),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let participants: ParticipantAccountsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        participants.participants,
+        vec![ParticipantAccountResponse {
+            address: String::from("ido_address_3"),
+            info: ParticipantResponse {
+                is_joined: true,
+                tier: None,
+                weight: 0,
+                lottery: false,
+                committed: Uint128::from(500u128),
+                phase: None,
+            },
+        }]
+    );
+}
+
+
+#[test]
+fn settle_allocations_and_claim_ido_tokens() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            vec![(
+                     String::from("ido_address_1"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(1000_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 ),
+                 (
+                     String::from("ido_address_2"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(500_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 )],
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            vec![(
+                     String::from("ido_address_1"),
+                     (true, true),
+                 ), (
+                     String::from("ido_address_2"),
+                     (true, true),
+                 )],
+        )],
+    );
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+
+    // settlement is only possible after the sale
+    let msg = ExecuteMsg::SettleAllocations { limit: None };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg.clone());
+    match res {
+        Err(ContractError::IdoNotClosed {}) => {}
+        _ => panic!("Must return IDO is not closed yet"),
+    }
+
+    // and once the snapshot time is reached
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100001);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg.clone());
+    match res {
+        Err(ContractError::SnapshotNotReached {}) => {}
+        _ => panic!("Must return snapshot not reached"),
+    }
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: Some(200000u64),
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), update_msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(200001);
+
+    // only owner can settle
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // settlement reads the participants snapshot
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg.clone());
+    match res {
+        Err(ContractError::SnapshotNotTaken {}) => {}
+        _ => panic!("Must return snapshot not taken"),
+    }
+
+    // anyone can take the snapshot
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: Some(1) }).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "take_snapshot"),
+            attr("snapshot_participants", "1"),
+            attr("is_snapshot_taken", "false"),
+        ]
+    );
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: None }).unwrap();
+    assert_eq!(res.attributes[1], attr("snapshot_participants", "1"));
+    assert_eq!(res.attributes[2], attr("is_snapshot_taken", "true"));
+    assert_eq!(
+        from_binary::<Option<ParticipantSnapshot>>(&query(deps.as_ref(), mock_env(), QueryMsg::Snapshot {
+            address: String::from("ido_address_2"),
+        }).unwrap()).unwrap(),
+        Some(ParticipantSnapshot {
+            available_funds: Uint128::from(500_000000u128),
+            is_verified: true,
+            is_accepted: true,
+            bond_amount: Uint128::zero(),
+        })
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: Some(1) }).unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<CosmosMsg>>(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("prefund_addr"),
+            funds: vec![],
+            msg: to_binary(&PrefundExecuteMsg::PayForIdo {
+                funder_reqs: vec![FunderRequest {
+                    addr: String::from("ido_address_1"),
+                    amount: Uint128::from(1000_000000u128),
+                }],
+            }).unwrap(),
+        })]
+    );
+
+    // claim is not possible until every participant is settled
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::ClaimIdoTokens {});
+    match res {
+        Err(ContractError::NotSettled {}) => {}
+        _ => panic!("Must return allocations are not settled yet"),
+    }
+
+    // sale can not be cancelled once funds are being collected
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::CancelIdo {});
+    match res {
+        Err(ContractError::SettlementStarted {}) => {}
+        _ => panic!("Must return settlement already started"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: Some(1) }).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "settle_allocations"),
+            attr("settled_participants", "1"),
+            attr("is_settled", "false"),
+        ]
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: Some(1) }).unwrap();
+    assert_eq!(res.messages.len(), 0);
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "settle_allocations"),
+            attr("settled_participants", "0"),
+            attr("is_settled", "true"),
+        ]
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg);
+    match res {
+        Err(ContractError::AlreadySettled {}) => {}
+        _ => panic!("Must return allocations are already settled"),
+    }
+
+    assert_eq!(
+        from_binary::<StateResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap(),
+        StateResponse {
+            number_of_participants: 2,
+            total_allocation: Uint128::from(1500_000000u128),
+            raised: Uint128::from(1500_000000u128),
+            remaining: None,
+            percent_filled: None,
+            is_snapshot_taken: true,
+            is_settled: true,
+        }
+    );
+
+    // 1000 UST at 0.05 UST per token
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::ClaimIdoTokens {}).unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<CosmosMsg>>(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("ido_token_address"),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("ido_address_1"),
+                amount: Uint128::from(20000_000000u128),
+            }).unwrap(),
+        })]
+    );
+
+    assert_eq!(
+        from_binary::<AllocationInfo>(&query(deps.as_ref(), mock_env(), QueryMsg::Allocation {
+            address: String::from("ido_address_1"),
+        }).unwrap()).unwrap(),
+        AllocationInfo {
+            address: String::from("ido_address_1"),
+            allocation: Uint128::from(1000_000000u128),
+            ido_token_amount: Uint128::from(20000_000000u128),
+            claimed_amount: Uint128::from(20000_000000u128),
+        }
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::ClaimIdoTokens {});
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("Must return nothing to claim"),
+    }
+
+    let res = execute(deps.as_mut(), env, mock_info("ido_address_3", &[]), ExecuteMsg::ClaimIdoTokens {});
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("Must return nothing to claim"),
+    }
 }
 
+#[test]
+fn join_ido_with_tiers() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            vec![(
+                     String::from("ido_address_1"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(1000_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 ),
+                 (
+                     String::from("ido_address_2"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(1000_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 ),
+                 (
+                     String::from("ido_address_3"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(200_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 )],
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            vec![(
+                     String::from("ido_address_1"),
+                     (true, true),
+                 ), (
+                     String::from("ido_address_2"),
+                     (true, true),
+                 ), (
+                     String::from("ido_address_3"),
+                     (true, true),
+                 )],
+        )],
+    );
+    deps.querier.with_bond_amounts(vec![(
+        String::from("staking_gateway_address"),
+        vec![
+            (String::from("ido_address_1"), Uint128::from(50u128)),
+            (String::from("ido_address_2"), Uint128::from(500u128)),
+            (String::from("ido_address_3"), Uint128::from(5000u128)),
+        ],
+    )]);
+
+    let tiers = vec![
+        Tier { tier: 1, min_bond: Uint128::from(100u128), weight: 1, lottery: false },
+        Tier { tier: 2, min_bond: Uint128::from(1000u128), weight: 3, lottery: false },
+    ];
+    let mut msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![tiers[1].clone(), tiers[0].clone()],
+        allocation_per_weight: Uint128::from(100_000000u128),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    let res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::InvalidTiers {}) => {}
+        _ => panic!("Must return invalid tiers"),
+    }
+
+    msg.tiers = tiers;
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // bonded less than the lowest tier
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None });
+    match res {
+        Err(ContractError::NotEligibleToJoinIdo {}) => {}
+        _ => panic!("Must return not eligible to join IDO"),
+    }
+
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+
+    assert_eq!(
+        from_binary::<ParticipantResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::FunderInfo {
+            address: String::from("ido_address_3"),
+        }).unwrap()).unwrap(),
+        ParticipantResponse {
+            is_joined: true,
+            tier: Some(2),
+            weight: 3,
+            lottery: false,
+            committed: Uint128::from(200_000000u128),
+            phase: None,
+        }
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::ParticipantAccounts {
+        start_after: None,
+        limit: None,
+        order_by: Some(OrderBy::Asc),
+    }).unwrap();
+    let participants: ParticipantAccountsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        participants.participants.iter().map(|user| (user.address.as_str(), user.info.tier)).collect::<Vec<(&str, Option<u32>)>>(),
+        vec![("ido_address_2", Some(1)), ("ido_address_3", Some(2))]
+    );
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: Some(200000u64),
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env, mock_info("owner", &[]), update_msg).unwrap();
+
+    // tier allocation caps the prefund balance
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(200001);
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: None }).unwrap();
+    execute(deps.as_mut(), env, mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: None }).unwrap();
+
+    assert_eq!(
+        from_binary::<AllocationInfo>(&query(deps.as_ref(), mock_env(), QueryMsg::Allocation {
+            address: String::from("ido_address_2"),
+        }).unwrap()).unwrap().allocation,
+        Uint128::from(100_000000u128)
+    );
+    assert_eq!(
+        from_binary::<AllocationInfo>(&query(deps.as_ref(), mock_env(), QueryMsg::Allocation {
+            address: String::from("ido_address_3"),
+        }).unwrap()).unwrap().allocation,
+        Uint128::from(200_000000u128)
+    );
+}
+
+#[test]
+fn lottery_tier() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    let users = ["ido_address_1", "ido_address_2", "ido_address_3", "ido_address_4"];
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            users.iter().map(|user| (String::from(*user), FunderInfoResponse {
+                available_funds: Uint128::from(1000_000000u128),
+                spent_funds: Uint128::zero(),
+            })).collect(),
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            users.iter().map(|user| (String::from(*user), (true, true))).collect(),
+        )],
+    );
+    deps.querier.with_bond_amounts(vec![(
+        String::from("staking_gateway_address"),
+        vec![(String::from("ido_address_4"), Uint128::from(2000u128))],
+    )]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![
+            Tier { tier: 0, min_bond: Uint128::zero(), weight: 1, lottery: true },
+            Tier { tier: 1, min_bond: Uint128::from(1000u128), weight: 2, lottery: false },
+        ],
+        allocation_per_weight: Uint128::from(100_000000u128),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    let _res = instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    for user in users.iter() {
+        execute(deps.as_mut(), env.clone(), mock_info(user, &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    }
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: Some(200000u64),
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), update_msg).unwrap();
+
+    let seed = String::from("starterra lottery seed");
+    let seed_hash = sha256_hex(seed.as_bytes());
+    let commit_msg = ExecuteMsg::CommitLotterySeed {
+        seed_hash: seed_hash.clone(),
+        winning_tickets: 1,
+    };
+
+    // seed can be committed only after the sale
+    let res = execute(deps.as_mut(), env, mock_info("owner", &[]), commit_msg.clone());
+    match res {
+        Err(ContractError::IdoNotClosed {}) => {}
+        _ => panic!("Must return IDO is not closed yet"),
+    }
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(200001);
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: None }).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: None });
+    match res {
+        Err(ContractError::LotteryNotDrawn {}) => {}
+        _ => panic!("Must return lottery is not drawn yet"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), commit_msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::CommitLotterySeed {
+        seed_hash: String::from("not a hash"),
+        winning_tickets: 1,
+    });
+    match res {
+        Err(ContractError::InvalidSeedHash {}) => {}
+        _ => panic!("Must return invalid seed hash"),
+    }
+
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), commit_msg.clone()).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), commit_msg);
+    match res {
+        Err(ContractError::LotterySeedAlreadyCommitted {}) => {}
+        _ => panic!("Must return lottery seed is already committed"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::DrawLottery { limit: None });
+    match res {
+        Err(ContractError::LotterySeedNotRevealed {}) => {}
+        _ => panic!("Must return lottery seed is not revealed"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::RevealLotterySeed { seed: seed.clone() });
+    match res {
+        Err(ContractError::LotteryRevealTooEarly {}) => {}
+        _ => panic!("Must return lottery reveal too early"),
+    }
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(200010);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::RevealLotterySeed { seed: String::from("other seed") });
+    match res {
+        Err(ContractError::LotterySeedMismatch {}) => {}
+        _ => panic!("Must return lottery seed mismatch"),
+    }
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::RevealLotterySeed { seed: seed.clone() }).unwrap();
+
+    // anyone can draw, page by page
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::DrawLottery { limit: Some(2) }).unwrap();
+    assert_eq!(res.attributes[2], attr("is_drawn", "false"));
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::DrawLottery { limit: Some(2) }).unwrap();
+    assert_eq!(res.attributes[2], attr("is_drawn", "false"));
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::DrawLottery { limit: Some(2) }).unwrap();
+    assert_eq!(res.attributes[2], attr("is_drawn", "true"));
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::DrawLottery { limit: None });
+    match res {
+        Err(ContractError::LotteryAlreadyDrawn {}) => {}
+        _ => panic!("Must return lottery is already drawn"),
+    }
+
+    assert_eq!(
+        from_binary::<LotteryResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Lottery {}).unwrap()).unwrap(),
+        LotteryResponse {
+            seed_hash: Some(seed_hash),
+            seed: Some(seed),
+            entrants: 3,
+            winning_tickets: 1,
+            winners: 1,
+            is_drawn: true,
+        }
+    );
+
+    let results: Vec<LotteryResultResponse> = users.iter().map(|user| {
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::LotteryResult {
+            address: String::from(*user),
+        }).unwrap()).unwrap()
+    }).collect();
+    assert_eq!(results.iter().filter(|result| result.is_entrant).count(), 3);
+    assert_eq!(results.iter().filter(|result| result.is_winner).count(), 1);
+    assert_eq!(
+        results[3],
+        LotteryResultResponse {
+            address: String::from("ido_address_4"),
+            is_entrant: false,
+            is_drawn: true,
+            is_winner: false,
+        }
+    );
+
+    // anyone can replay the draw with the revealed seed over the entrants in ParticipantAccounts order
+    let revealed_seed = from_binary::<LotteryResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Lottery {}).unwrap()).unwrap()
+        .seed.unwrap();
+    let accounts = from_binary::<ParticipantAccountsResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::ParticipantAccounts {
+        start_after: None,
+        limit: None,
+        order_by: Some(OrderBy::Asc),
+    }).unwrap()).unwrap();
+    let mut remaining_entrants = 3u64;
+    let mut remaining_tickets = 1u64;
+    for account in accounts.participants.iter().filter(|account| account.info.lottery) {
+        let is_winner = lottery_ticket(&revealed_seed, &account.address) % remaining_entrants < remaining_tickets;
+        let result = results.iter().find(|result| result.address == account.address).unwrap();
+        assert_eq!(result.is_winner, is_winner);
+        if is_winner {
+            remaining_tickets -= 1;
+        }
+        remaining_entrants -= 1;
+    }
+    assert_eq!(remaining_entrants, 0);
+
+    execute(deps.as_mut(), env, mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: None }).unwrap();
+    for result in results.iter() {
+        let allocation = from_binary::<AllocationInfo>(&query(deps.as_ref(), mock_env(), QueryMsg::Allocation {
+            address: result.address.clone(),
+        }).unwrap()).unwrap().allocation;
+        let expected = if !result.is_entrant {
+            Uint128::from(200_000000u128)
+        } else if result.is_winner {
+            Uint128::from(100_000000u128)
+        } else {
+            Uint128::zero()
+        };
+        assert_eq!(allocation, expected);
+    }
+}
+
+#[test]
+fn lottery_keeps_soft_cap_met_at_end_date() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    let users = ["ido_address_1", "ido_address_2", "ido_address_3", "ido_address_4"];
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            users.iter().map(|user| (String::from(*user), FunderInfoResponse {
+                available_funds: Uint128::from(1000_000000u128),
+                spent_funds: Uint128::zero(),
+            })).collect(),
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            users.iter().map(|user| (String::from(*user), (true, true))).collect(),
+        )],
+    );
+    deps.querier.with_bond_amounts(vec![(
+        String::from("staking_gateway_address"),
+        vec![(String::from("ido_address_4"), Uint128::from(2000u128))],
+    )]);
+
+    // three lottery entrants commit 100 each and ido_address_4 commits 200, exactly the soft cap
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![
+            Tier { tier: 0, min_bond: Uint128::zero(), weight: 1, lottery: true },
+            Tier { tier: 1, min_bond: Uint128::from(1000u128), weight: 2, lottery: false },
+        ],
+        allocation_per_weight: Uint128::from(100_000000u128),
+        soft_cap: Uint128::from(500_000000u128),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    let _res = instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    for user in users.iter() {
+        execute(deps.as_mut(), env.clone(), mock_info(user, &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    }
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: Some(100000u64),
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env, mock_info("owner", &[]), update_msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100001);
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: None }).unwrap();
+
+    let seed = String::from("starterra lottery seed");
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::CommitLotterySeed {
+        seed_hash: sha256_hex(seed.as_bytes()),
+        winning_tickets: 1,
+    }).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100010);
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::RevealLotterySeed { seed }).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::DrawLottery { limit: None }).unwrap();
+
+    // the two losers released 200, the raised amount drops below the soft cap
+    let state = from_binary::<StateResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(state.raised, Uint128::from(300_000000u128));
+
+    // but the sale still met its soft cap at end_date
+    let status = from_binary::<StatusResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::Status { block_time: None }).unwrap()).unwrap();
+    assert!(!status.is_cancelled);
+
+    execute(deps.as_mut(), env, mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: None }).unwrap();
+    let state = from_binary::<StateResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert!(state.is_settled);
+    assert_eq!(state.total_allocation, Uint128::from(300_000000u128));
+}
+
+#[test]
+fn cancel_ido_and_soft_cap() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            vec![(
+                     String::from("ido_address_1"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(1000_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 ),
+                 (
+                     String::from("ido_address_2"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(500_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 )],
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            vec![(
+                     String::from("ido_address_1"),
+                     (true, true),
+                 ), (
+                     String::from("ido_address_2"),
+                     (true, true),
+                 )],
+        )],
+    );
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::from(2000_000000u128),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    let _res = instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+
+    // soft cap is only checked once the sale is over
+    let status = from_binary::<StatusResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Status { block_time: Some(100u64) }).unwrap()).unwrap();
+    assert!(!status.is_cancelled);
+    let status = from_binary::<StatusResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Status { block_time: Some(100001u64) }).unwrap()).unwrap();
+    assert!(status.is_cancelled);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: Some(200000u64),
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: Some(Uint128::from(1000_000000u128)),
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+    let status = from_binary::<StatusResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Status { block_time: Some(100001u64) }).unwrap()).unwrap();
+    assert!(!status.is_cancelled);
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::CancelIdo {});
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::CancelIdo {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "cancel_ido"),
+            attr("released", "1000000000"),
+        ]
+    );
+    assert!(res.messages.is_empty());
+
+    let status = from_binary::<StatusResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Status { block_time: Some(100u64) }).unwrap()).unwrap();
+    assert!(status.is_cancelled);
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: None });
+    match res {
+        Err(ContractError::IdoCancelled {}) => {}
+        _ => panic!("Must return IDO is cancelled"),
+    }
+
+    let res = execute(deps.as_mut(), env, mock_info("owner", &[]), ExecuteMsg::CancelIdo {});
+    match res {
+        Err(ContractError::IdoCancelled {}) => {}
+        _ => panic!("Must return IDO is cancelled"),
+    }
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(200001);
+    let res = execute(deps.as_mut(), env, mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: None });
+    match res {
+        Err(ContractError::IdoCancelled {}) => {}
+        _ => panic!("Must return IDO is cancelled"),
+    }
+}
+
+#[test]
+fn join_ido_in_phases() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    let users = ["ido_address_1", "ido_address_2", "ido_address_3", "ido_address_4"];
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            users.iter().map(|user| (String::from(*user), FunderInfoResponse {
+                available_funds: Uint128::from(if *user == "ido_address_3" { 500u128 } else { 1000u128 }),
+                spent_funds: Uint128::zero(),
+            })).collect(),
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            users.iter().map(|user| (String::from(*user), (true, true))).collect(),
+        )],
+    );
+    deps.querier.with_bond_amounts(vec![(
+        String::from("staking_gateway_address"),
+        vec![(String::from("ido_address_1"), Uint128::from(100u128))],
+    )]);
+
+    let phases = vec![
+        Phase {
+            name: String::from("whitelist"),
+            start_time: 1000,
+            end_time: 1999,
+            minimum_prefund: None,
+            stakers_only: true,
+            max_participants: None,
+        },
+        Phase {
+            name: String::from("public"),
+            start_time: 2000,
+            end_time: 2999,
+            minimum_prefund: Some(Uint128::from(600u128)),
+            stakers_only: false,
+            max_participants: None,
+        },
+        Phase {
+            name: String::from("fcfs"),
+            start_time: 3000,
+            end_time: 3999,
+            minimum_prefund: None,
+            stakers_only: false,
+            max_participants: Some(1),
+        },
+    ];
+    let mut msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![phases[1].clone(), phases[0].clone()],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    let res = instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg.clone());
+    match res {
+        Err(ContractError::InvalidPhases {}) => {}
+        _ => panic!("Must return invalid phases"),
+    }
+    msg.phases = phases;
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let status = from_binary::<StatusResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Status { block_time: Some(500u64) }).unwrap()).unwrap();
+    assert_eq!(status.current_phase, None);
+    assert_eq!(status.time_to_next_phase, Some(500u64));
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(500);
+    let res = execute(deps.as_mut(), env, mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None });
+    match res {
+        Err(ContractError::NoActivePhase {}) => {}
+        _ => panic!("Must return no active phase"),
+    }
+
+    // whitelist phase is for stakers only
+    let status = from_binary::<StatusResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Status { block_time: Some(1500u64) }).unwrap()).unwrap();
+    assert_eq!(status.current_phase, Some(String::from("whitelist")));
+    assert_eq!(status.time_to_next_phase, Some(500u64));
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(1500);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: None });
+    match res {
+        Err(ContractError::NotEligibleToJoinIdo {}) => {}
+        _ => panic!("Must return not eligible to join IDO"),
+    }
+    execute(deps.as_mut(), env, mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    assert_eq!(
+        from_binary::<ParticipantResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::FunderInfo {
+            address: String::from("ido_address_1"),
+        }).unwrap()).unwrap().phase,
+        Some(0)
+    );
+
+    // public phase has its own minimum prefund
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(2500);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::JoinIdo { referrer: None });
+    match res {
+        Err(ContractError::NotEnoughDeposit {}) => {}
+        _ => panic!("Must return not enough deposit"),
+    }
+    execute(deps.as_mut(), env, mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+
+    // first come first served phase has a single slot
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(3500);
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_4", &[]), ExecuteMsg::JoinIdo { referrer: None });
+    match res {
+        Err(ContractError::PhaseFull {}) => {}
+        _ => panic!("Must return phase is full"),
+    }
+
+    let status = from_binary::<StatusResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Status { block_time: Some(3500u64) }).unwrap()).unwrap();
+    assert_eq!(status.current_phase, Some(String::from("fcfs")));
+    assert_eq!(status.time_to_next_phase, None);
+
+    // end date can not cut the last phase
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: Some(3600u64),
+        paused: None,
+        snapshot_time: None,
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    let res = execute(deps.as_mut(), env, mock_info("owner", &[]), msg);
+    match res {
+        Err(ContractError::InvalidPhases {}) => {}
+        _ => panic!("Must return invalid phases"),
+    }
+}
+
+#[test]
+fn join_ido_with_hard_cap() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    let users = ["ido_address_1", "ido_address_2", "ido_address_3"];
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            users.iter().map(|user| (String::from(*user), FunderInfoResponse {
+                available_funds: Uint128::from(if *user == "ido_address_1" { 1000_000000u128 } else { 500_000000u128 }),
+                spent_funds: Uint128::zero(),
+            })).collect(),
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            users.iter().map(|user| (String::from(*user), (true, true))).collect(),
+        )],
+    );
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::from(1250_000000u128),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    assert_eq!(
+        from_binary::<StateResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap(),
+        StateResponse {
+            number_of_participants: 1,
+            total_allocation: Uint128::zero(),
+            raised: Uint128::from(1000_000000u128),
+            remaining: Some(Uint128::from(250_000000u128)),
+            percent_filled: Some(Decimal::from_ratio(80u128, 1u128)),
+            is_snapshot_taken: false,
+            is_settled: false,
+        }
+    );
+
+    // the last participant is filled partially
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    assert_eq!(
+        from_binary::<ParticipantResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::FunderInfo {
+            address: String::from("ido_address_2"),
+        }).unwrap()).unwrap().committed,
+        Uint128::from(250_000000u128)
+    );
+    assert_eq!(
+        from_binary::<StateResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap(),
+        StateResponse {
+            number_of_participants: 2,
+            total_allocation: Uint128::zero(),
+            raised: Uint128::from(1250_000000u128),
+            remaining: Some(Uint128::zero()),
+            percent_filled: Some(Decimal::from_ratio(100u128, 1u128)),
+            is_snapshot_taken: false,
+            is_settled: false,
+        }
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::JoinIdo { referrer: None });
+    match res {
+        Err(ContractError::HardCapReached {}) => {}
+        _ => panic!("Must return hard cap reached"),
+    }
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: Some(200000u64),
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env, mock_info("owner", &[]), msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(200001);
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: None }).unwrap();
+    let res = execute(deps.as_mut(), env, mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: None }).unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<CosmosMsg>>(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("prefund_addr"),
+            funds: vec![],
+            msg: to_binary(&PrefundExecuteMsg::PayForIdo {
+                funder_reqs: vec![
+                    FunderRequest {
+                        addr: String::from("ido_address_1"),
+                        amount: Uint128::from(1000_000000u128),
+                    },
+                    FunderRequest {
+                        addr: String::from("ido_address_2"),
+                        amount: Uint128::from(250_000000u128),
+                    },
+                ],
+            }).unwrap(),
+        })]
+    );
+}
+
+#[test]
+fn claim_vested_ido_tokens() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            vec![(
+                String::from("ido_address_1"),
+                FunderInfoResponse {
+                    available_funds: Uint128::from(1000_000000u128),
+                    spent_funds: Uint128::zero(),
+                }
+            )],
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            vec![(String::from("ido_address_1"), (true, true))],
+        )],
+    );
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: Some(VestingSchedule {
+            tge_time: 300000,
+            tge_unlock: Decimal::from_ratio(3u128, 2u128),
+            cliff: 1000,
+            duration: 10000,
+        }),
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    let res = instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg.clone());
+    match res {
+        Err(ContractError::InvalidVestingSchedule {}) => {}
+        _ => panic!("Must return invalid vesting schedule"),
+    }
+
+    let vesting = VestingSchedule {
+        tge_time: 300000,
+        tge_unlock: Decimal::from_ratio(1u128, 4u128),
+        cliff: 1000,
+        duration: 10000,
+    };
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), InstantiateMsg {
+        vesting: Some(vesting.clone()),
+        ..msg
+    }).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: Some(200000u64),
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env, mock_info("owner", &[]), msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(200001);
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: None }).unwrap();
+    execute(deps.as_mut(), env, mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: None }).unwrap();
+
+    // nothing is unlocked before tge
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(250000);
+    let res = execute(deps.as_mut(), env, mock_info("ido_address_1", &[]), ExecuteMsg::ClaimIdoTokens {});
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("Must return nothing to claim"),
+    }
+
+    assert_eq!(
+        from_binary::<VestingAccountResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::ClaimableAt {
+            address: String::from("ido_address_1"),
+            block_time: Some(300000u64),
+        }).unwrap()).unwrap(),
+        VestingAccountResponse {
+            address: String::from("ido_address_1"),
+            info: VestingInfo {
+                amount: Uint128::from(20000_000000u128),
+                already_claimed: Uint128::zero(),
+            },
+            possible_claim: Uint128::from(5000_000000u128),
+        }
+    );
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(300500);
+    let res = execute(deps.as_mut(), env, mock_info("ido_address_1", &[]), ExecuteMsg::ClaimIdoTokens {}).unwrap();
+    assert_eq!(res.attributes[2], attr("amount", "5000000000"));
+
+    // cliff
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(301000);
+    let res = execute(deps.as_mut(), env, mock_info("ido_address_1", &[]), ExecuteMsg::ClaimIdoTokens {});
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("Must return nothing to claim"),
+    }
+
+    // half of the linear part
+    assert_eq!(
+        from_binary::<VestingAccountResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::ClaimableAt {
+            address: String::from("ido_address_1"),
+            block_time: Some(306000u64),
+        }).unwrap()).unwrap().possible_claim,
+        Uint128::from(7500_000000u128)
+    );
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(311000);
+    let res = execute(deps.as_mut(), env, mock_info("ido_address_1", &[]), ExecuteMsg::ClaimIdoTokens {}).unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<CosmosMsg>>(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("ido_token_address"),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("ido_address_1"),
+                amount: Uint128::from(15000_000000u128),
+            }).unwrap(),
+        })]
+    );
+
+    assert_eq!(
+        from_binary::<VestingAccountResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::ClaimableAt {
+            address: String::from("ido_address_1"),
+            block_time: Some(400000u64),
+        }).unwrap()).unwrap(),
+        VestingAccountResponse {
+            address: String::from("ido_address_1"),
+            info: VestingInfo {
+                amount: Uint128::from(20000_000000u128),
+                already_claimed: Uint128::from(20000_000000u128),
+            },
+            possible_claim: Uint128::zero(),
+        }
+    );
+}
+
+#[test]
+fn leave_ido() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            vec![(
+                     String::from("ido_address_1"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(1000_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 ),
+                 (
+                     String::from("ido_address_2"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(500_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 )],
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            vec![(
+                     String::from("ido_address_1"),
+                     (true, true),
+                 ), (
+                     String::from("ido_address_2"),
+                     (true, true),
+                 )],
+        )],
+    );
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::from(10_000000u128),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::LeaveIdo {});
+    match res {
+        Err(ContractError::NotJoined {}) => {}
+        _ => panic!("Must return not joined"),
+    }
+
+    // penalty has to be paid
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::LeaveIdo {});
+    match res {
+        Err(ContractError::Std(_)) => {}
+        _ => panic!("Must return UST balance sent to low"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &coins(15_000000u128, "uusd")), ExecuteMsg::LeaveIdo {}).unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<CosmosMsg>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("treasury_address"),
+                amount: coins(10_000000u128, "uusd"),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("ido_address_1"),
+                amount: coins(5_000000u128, "uusd"),
+            }),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "leave_ido"),
+            attr("address", "ido_address_1"),
+            attr("penalty", "10000000"),
+        ]
+    );
+
+    assert_eq!(
+        from_binary::<ParticipantResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::FunderInfo {
+            address: String::from("ido_address_1"),
+        }).unwrap()).unwrap(),
+        ParticipantResponse::default()
+    );
+    let state = from_binary::<StateResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(state.number_of_participants, 1);
+    assert_eq!(state.raised, Uint128::from(500_000000u128));
+    let participants = from_binary::<ParticipantsResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Participants {
+        start_after: None,
+        limit: None,
+        order_by: None,
+    }).unwrap()).unwrap();
+    assert_eq!(participants.users, vec![String::from("ido_address_2")]);
+
+    // and can join again while the sale is open
+    execute(deps.as_mut(), env, mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100001);
+    let res = execute(deps.as_mut(), env, mock_info("ido_address_2", &coins(10_000000u128, "uusd")), ExecuteMsg::LeaveIdo {});
+    match res {
+        Err(ContractError::UserJoinedAndCantBeEdited {}) => {}
+        _ => panic!("Must return user joined and cant be edited"),
+    }
+}
+
+#[test]
+fn settle_allocations_from_snapshot() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            vec![(
+                     String::from("ido_address_1"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(1000_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 ),
+                 (
+                     String::from("ido_address_2"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(500_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 )],
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            vec![(
+                     String::from("ido_address_1"),
+                     (true, true),
+                 ), (
+                     String::from("ido_address_2"),
+                     (true, true),
+                 )],
+        )],
+    );
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 200000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+
+    let mut update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: Some(150000u64),
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), update_msg.clone()).unwrap();
+
+    let res = execute(deps.as_mut(), env, mock_info("ido_address_1", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: None });
+    match res {
+        Err(ContractError::SnapshotNotReached {}) => {}
+        _ => panic!("Must return snapshot not reached"),
+    }
+
+    // participants are frozen once the snapshot time is reached
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(150001);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::JoinIdo { referrer: None });
+    match res {
+        Err(ContractError::SnapshotTimeReached {}) => {}
+        _ => panic!("Must return snapshot time reached"),
+    }
+
+    // a top up after joining is not allocated, even when the snapshot is taken later
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            vec![(
+                     String::from("ido_address_1"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(3000_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 ),
+                 (
+                     String::from("ido_address_2"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(500_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 )],
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            vec![(
+                     String::from("ido_address_1"),
+                     (true, true),
+                 ), (
+                     String::from("ido_address_2"),
+                     (true, true),
+                 )],
+        )],
+    );
+
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: None }).unwrap();
+
+    if let ExecuteMsg::UpdateConfig { ref mut snapshot_time, .. } = update_msg {
+        *snapshot_time = Some(160000u64);
+    }
+    let res = execute(deps.as_mut(), env, mock_info("owner", &[]), update_msg);
+    match res {
+        Err(ContractError::SnapshotStarted {}) => {}
+        _ => panic!("Must return snapshot started"),
+    }
+
+    // balance and kyc changes after the snapshot do not change the settlement
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            vec![(
+                     String::from("ido_address_1"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(2000_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 ),
+                 (
+                     String::from("ido_address_2"),
+                     FunderInfoResponse {
+                         available_funds: Uint128::from(100_000000u128),
+                         spent_funds: Uint128::zero(),
+                     }
+                 )],
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            vec![],
+        )],
+    );
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(200001);
+    let res = execute(deps.as_mut(), env, mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: None }).unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<CosmosMsg>>(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("prefund_addr"),
+            funds: vec![],
+            msg: to_binary(&PrefundExecuteMsg::PayForIdo {
+                funder_reqs: vec![FunderRequest {
+                    addr: String::from("ido_address_1"),
+                    amount: Uint128::from(1000_000000u128),
+                }, FunderRequest {
+                    addr: String::from("ido_address_2"),
+                    amount: Uint128::from(500_000000u128),
+                }],
+            }).unwrap(),
+        })]
+    );
+    assert_eq!(
+        from_binary::<AllocationInfo>(&query(deps.as_ref(), mock_env(), QueryMsg::Allocation {
+            address: String::from("ido_address_2"),
+        }).unwrap()).unwrap().allocation,
+        Uint128::from(500_000000u128)
+    );
+}
+
+#[test]
+fn prefund_with_swap() {
+    let mut deps = mock_dependencies(&coins(100_000000u128, "uusd"), MOCK_CONTRACT_ADDR);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let luna_to_ust = vec![SwapOperation::NativeSwap {
+        offer_denom: String::from("uluna"),
+        ask_denom: String::from("uusd"),
+    }];
+
+    let prefund_balance = |amount: u128| vec![(
+        String::from("prefund_addr"),
+        vec![(String::from(MOCK_CONTRACT_ADDR), FunderInfoResponse {
+            available_funds: Uint128::from(amount),
+            spent_funds: Uint128::zero(),
+        })],
+    )];
+    deps.querier.with_account_statuses(prefund_balance(0u128), vec![]);
+
+    // uusd is never routed through a swap
+    let msg = ExecuteMsg::Prefund {
+        operations: vec![
+            SwapOperation::NativeSwap {
+                offer_denom: String::from("uusd"),
+                ask_denom: String::from("uluna"),
+            },
+            SwapOperation::NativeSwap {
+                offer_denom: String::from("uluna"),
+                ask_denom: String::from("uusd"),
+            },
+        ],
+        minimum_receive: Uint128::from(1_000000u128),
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &coins(1_000000u128, "uusd")), msg);
+    match res {
+        Err(ContractError::InvalidSwapOperations {}) => {}
+        _ => panic!("Must return invalid swap operations error"),
+    }
+
+    // operations have to start with the sent asset and end in uusd
+    let msg = ExecuteMsg::Prefund {
+        operations: vec![SwapOperation::NativeSwap {
+            offer_denom: String::from("uluna"),
+            ask_denom: String::from("ukrw"),
+        }],
+        minimum_receive: Uint128::from(90_000000u128),
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &coins(1_000000u128, "uluna")), msg);
+    match res {
+        Err(ContractError::InvalidSwapOperations {}) => {}
+        _ => panic!("Must return invalid swap operations error"),
+    }
+
+    let msg = ExecuteMsg::Prefund {
+        operations: luna_to_ust.clone(),
+        minimum_receive: Uint128::from(90_000000u128),
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), msg.clone());
+    match res {
+        Err(ContractError::InvalidPrefundFunds {}) => {}
+        _ => panic!("Must return invalid prefund funds error"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &coins(1_000000u128, "uluna")), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: String::from("router0000"),
+                msg: to_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                    operations: luna_to_ust,
+                    minimum_receive: Some(Uint128::from(90_000000u128)),
+                    to: None,
+                }).unwrap(),
+                funds: coins(1_000000u128, "uluna"),
+            },
+            1,
+        )]
+    );
+
+    // router sent 95 UST back, the difference is deposited under the IDO contract
+    deps.querier.with_balance(MOCK_CONTRACT_ADDR, coins(195_000000u128, "uusd"));
+    let swap_reply = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse { events: vec![], data: None }),
+    };
+    let res = reply(deps.as_mut(), env.clone(), swap_reply.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: String::from("prefund_addr"),
+                msg: to_binary(&PrefundExecuteMsg::Deposit {}).unwrap(),
+                funds: coins(95_000000u128, "uusd"),
+            },
+            2,
+        )]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "prefund"),
+            attr("address", "ido_address_1"),
+            attr("amount", "95000000"),
+        ]
+    );
+
+    // the deposit leaves the contract
+    deps.querier.with_balance(MOCK_CONTRACT_ADDR, coins(100_000000u128, "uusd"));
+
+    // the prefund took a fee, only the deposited difference is credited to the sender
+    deps.querier.with_account_statuses(prefund_balance(94_000000u128), vec![]);
+    let deposit_reply = Reply {
+        id: 2,
+        result: ContractResult::Ok(SubMsgExecutionResponse { events: vec![], data: None }),
+    };
+    let res = reply(deps.as_mut(), env.clone(), deposit_reply.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "credit_prefund"),
+            attr("address", "ido_address_1"),
+            attr("amount", "94000000"),
+            attr("prefund_credit", "94000000"),
+        ]
+    );
+    assert_eq!(
+        from_binary::<PrefundCreditResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::PrefundCredit {
+            address: String::from("ido_address_1"),
+        }).unwrap()).unwrap(),
+        PrefundCreditResponse {
+            address: String::from("ido_address_1"),
+            amount: Uint128::from(94_000000u128),
+        }
+    );
+
+    // the pending prefund is consumed by the reply
+    let res = reply(deps.as_mut(), env.clone(), deposit_reply.clone());
+    match res {
+        Err(ContractError::UnknownReplyId {}) => {}
+        _ => panic!("Must return unknown reply id error"),
+    }
+
+    // a swap that returned nothing deposits nothing
+    let msg = ExecuteMsg::Prefund {
+        operations: vec![SwapOperation::NativeSwap {
+            offer_denom: String::from("uluna"),
+            ask_denom: String::from("uusd"),
+        }],
+        minimum_receive: Uint128::zero(),
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &coins(1_000000u128, "uluna")), msg).unwrap();
+    let res = reply(deps.as_mut(), env.clone(), swap_reply.clone());
+    match res {
+        Err(ContractError::NoSwappedPrefund {}) => {}
+        _ => panic!("Must return no swapped prefund error"),
+    }
+
+    // cw20 stablecoins are sent to the router with the swap hook
+    let aust_to_ust = vec![SwapOperation::TerraSwap {
+        offer_asset_info: AssetInfo::Token { contract_addr: String::from("aust_token") },
+        ask_asset_info: AssetInfo::NativeToken { denom: String::from("uusd") },
+    }];
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("ido_address_2"),
+        amount: Uint128::from(10_000000u128),
+        msg: to_binary(&Cw20HookMsg::Prefund {
+            operations: aust_to_ust.clone(),
+            minimum_receive: Uint128::from(11_000000u128),
+        }).unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), mock_info("aust_token", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: String::from("aust_token"),
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: String::from("router0000"),
+                    amount: Uint128::from(10_000000u128),
+                    msg: to_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                        operations: aust_to_ust,
+                        minimum_receive: Some(Uint128::from(11_000000u128)),
+                        to: None,
+                    }).unwrap(),
+                }).unwrap(),
+                funds: vec![],
+            },
+            1,
+        )]
+    );
+
+    // uusd is deposited without a swap
+    let msg = ExecuteMsg::Prefund {
+        operations: vec![],
+        minimum_receive: Uint128::zero(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &coins(20_000000u128, "uusd")), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: String::from("prefund_addr"),
+                msg: to_binary(&PrefundExecuteMsg::Deposit {}).unwrap(),
+                funds: coins(20_000000u128, "uusd"),
+            },
+            2,
+        )]
+    );
+
+    deps.querier.with_account_statuses(prefund_balance(114_000000u128), vec![]);
+    reply(deps.as_mut(), env.clone(), deposit_reply.clone()).unwrap();
+    assert_eq!(
+        from_binary::<PrefundCreditResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::PrefundCredit {
+            address: String::from("ido_address_3"),
+        }).unwrap()).unwrap().amount,
+        Uint128::from(20_000000u128)
+    );
+
+    // a credit is withdrawn back through the prefund and forwarded to its owner
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::WithdrawPrefundCredit {});
+    match res {
+        Err(ContractError::NoPrefundCredit {}) => {}
+        _ => panic!("Must return no prefund credit error"),
+    }
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::WithdrawPrefundCredit {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: String::from("prefund_addr"),
+                msg: to_binary(&PrefundExecuteMsg::WithdrawDeposit { amount: Uint128::from(20_000000u128) }).unwrap(),
+                funds: vec![],
+            },
+            3,
+        )]
+    );
+    deps.querier.with_balance(MOCK_CONTRACT_ADDR, coins(119_000000u128, "uusd"));
+    let res = reply(deps.as_mut(), env.clone(), Reply {
+        id: 3,
+        result: ContractResult::Ok(SubMsgExecutionResponse { events: vec![], data: None }),
+    }).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: String::from("ido_address_3"),
+            amount: coins(19_000000u128, "uusd"),
+        })]
+    );
+    assert_eq!(
+        from_binary::<PrefundCreditResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::PrefundCredit {
+            address: String::from("ido_address_3"),
+        }).unwrap()).unwrap().amount,
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn settle_allocations_with_prefund_credit() {
+    let mut deps = mock_dependencies(&coins(100_000000u128, "uusd"), MOCK_CONTRACT_ADDR);
+
+    let with_prefund_balance = |deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>, contract_balance: u128| {
+        deps.querier.with_account_statuses(
+            vec![(
+                String::from("prefund_addr"),
+                vec![(
+                         String::from("ido_address_1"),
+                         FunderInfoResponse {
+                             available_funds: Uint128::from(300_000000u128),
+                             spent_funds: Uint128::zero(),
+                         }
+                     ),
+                     (
+                         String::from(MOCK_CONTRACT_ADDR),
+                         FunderInfoResponse {
+                             available_funds: Uint128::from(contract_balance),
+                             spent_funds: Uint128::zero(),
+                         }
+                     )],
+            )],
+            vec![(
+                String::from("kyc_vault_address"),
+                vec![(
+                         String::from("ido_address_1"),
+                         (true, true),
+                     ), (
+                         String::from("ido_address_2"),
+                         (true, true),
+                     )],
+            )],
+        );
+    };
+    with_prefund_balance(&mut deps, 0u128);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 200000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let prefund = ExecuteMsg::Prefund {
+        operations: vec![],
+        minimum_receive: Uint128::zero(),
+    };
+    let deposit_reply = Reply {
+        id: 2,
+        result: ContractResult::Ok(SubMsgExecutionResponse { events: vec![], data: None }),
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &coins(200_000000u128, "uusd")), prefund.clone()).unwrap();
+    with_prefund_balance(&mut deps, 200_000000u128);
+    reply(deps.as_mut(), env.clone(), deposit_reply.clone()).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &coins(100_000000u128, "uusd")), prefund).unwrap();
+    with_prefund_balance(&mut deps, 300_000000u128);
+    reply(deps.as_mut(), env.clone(), deposit_reply).unwrap();
+
+    // the credit counts towards the prefund balance of the participant
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    assert_eq!(
+        from_binary::<ParticipantResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::FunderInfo {
+            address: String::from("ido_address_1"),
+        }).unwrap()).unwrap().committed,
+        Uint128::from(500_000000u128)
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::WithdrawPrefundCredit {});
+    match res {
+        Err(ContractError::PrefundCreditLocked {}) => {}
+        _ => panic!("Must return prefund credit locked error"),
+    }
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: Some(150000u64),
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env, mock_info("owner", &[]), update_msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(150001);
+    execute(deps.as_mut(), env, mock_info("ido_address_1", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: None }).unwrap();
+
+    // the credited share is paid from the prefund balance of the IDO contract
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(200001);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: None }).unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<CosmosMsg>>(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("prefund_addr"),
+            funds: vec![],
+            msg: to_binary(&PrefundExecuteMsg::PayForIdo {
+                funder_reqs: vec![FunderRequest {
+                    addr: String::from("ido_address_1"),
+                    amount: Uint128::from(300_000000u128),
+                }, FunderRequest {
+                    addr: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::from(300_000000u128),
+                }],
+            }).unwrap(),
+        })]
+    );
+
+    let res = execute(deps.as_mut(), env, mock_info("ido_address_1", &[]), ExecuteMsg::WithdrawPrefundCredit {});
+    match res {
+        Err(ContractError::NoPrefundCredit {}) => {}
+        _ => panic!("Must return no prefund credit error"),
+    }
+}
+
+#[test]
+fn join_ido_with_referrer() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    let funds = |amount: u128| FunderInfoResponse {
+        available_funds: Uint128::from(amount),
+        spent_funds: Uint128::zero(),
+    };
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            vec![
+                (String::from("ido_address_1"), funds(1000_000000u128)),
+                (String::from("ido_address_2"), funds(500_000000u128)),
+                (String::from("ido_address_3"), funds(300_000000u128)),
+                (String::from("ido_address_4"), funds(200_000000u128)),
+                (String::from("ido_address_5"), funds(100_000000u128)),
+            ],
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            vec![
+                (String::from("ido_address_1"), (true, true)),
+                (String::from("ido_address_2"), (true, true)),
+                (String::from("ido_address_3"), (true, true)),
+                (String::from("ido_address_4"), (true, true)),
+                (String::from("ido_address_5"), (true, true)),
+            ],
+        )],
+    );
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 200000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::from_ratio(Uint128::from(5u128), Uint128::from(100u128)),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // referrer has to be an IDO participant
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: Some(String::from("ido_address_1")) });
+    match res {
+        Err(ContractError::InvalidReferrer {}) => {}
+        _ => panic!("Must return invalid referrer error"),
+    }
+    // and can not refer itself
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: Some(String::from("ido_address_1")) });
+    match res {
+        Err(ContractError::InvalidReferrer {}) => {}
+        _ => panic!("Must return invalid referrer error"),
+    }
+
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), mock_info("ido_address_2", &[]), ExecuteMsg::JoinIdo { referrer: Some(String::from("ido_address_1")) }).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "join_ido"),
+            attr("address", "ido_address_2"),
+            attr("referrer", "ido_address_1"),
+        ]
+    );
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::JoinIdo { referrer: Some(String::from("ido_address_1")) }).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_4", &[]), ExecuteMsg::JoinIdo { referrer: Some(String::from("ido_address_2")) }).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_5", &[]), ExecuteMsg::JoinIdo { referrer: Some(String::from("ido_address_3")) }).unwrap();
+
+    // leaving removes the referral
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_3", &[]), ExecuteMsg::LeaveIdo {}).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Referrals { address: String::from("ido_address_2") }).unwrap();
+    let referrals: ReferralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        referrals,
+        ReferralsResponse {
+            address: String::from("ido_address_2"),
+            referrer: Some(String::from("ido_address_1")),
+            referees: 1,
+            reward: Uint128::zero(),
+        }
+    );
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Referrals { address: String::from("ido_address_1") }).unwrap();
+    let referrals: ReferralsResponse = from_binary(&res).unwrap();
+    assert_eq!(referrals.referees, 1);
+
+    // rewards are accrued at settlement
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: Some(150000u64),
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env, mock_info("owner", &[]), msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(200001);
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::TakeSnapshot { start_after: None, limit: None }).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::SettleAllocations { limit: None }).unwrap();
+
+    // ido_address_3 left, so its referee earns it nothing
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::ReferralLeaderboard { start_after: None, limit: None, order_by: None }).unwrap();
+    let leaderboard: ReferralLeaderboardResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        leaderboard.referrers,
+        vec![
+            ReferrerResponse {
+                address: String::from("ido_address_1"),
+                referees: 1,
+                reward: Uint128::from(25_000000u128),
+            },
+            ReferrerResponse {
+                address: String::from("ido_address_2"),
+                referees: 1,
+                reward: Uint128::from(10_000000u128),
+            },
+            ReferrerResponse {
+                address: String::from("ido_address_3"),
+                referees: 1,
+                reward: Uint128::zero(),
+            },
+        ]
+    );
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::ReferralLeaderboard {
+        start_after: Some(String::from("ido_address_1")),
+        limit: None,
+        order_by: Some(OrderBy::Desc),
+    }).unwrap();
+    let leaderboard: ReferralLeaderboardResponse = from_binary(&res).unwrap();
+    assert_eq!(leaderboard.referrers.len(), 2);
+    assert_eq!(leaderboard.referrers[0].address, "ido_address_2");
+
+    let res = query(deps.as_ref(), env, QueryMsg::ReferralLeaderboard {
+        start_after: None,
+        limit: Some(1),
+        order_by: Some(OrderBy::Asc),
+    }).unwrap();
+    let leaderboard: ReferralLeaderboardResponse = from_binary(&res).unwrap();
+    assert_eq!(leaderboard.referrers[0].address, "ido_address_3");
+}
+
+#[test]
+fn query_eligibility() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    deps.querier.with_account_statuses(
+        vec![(
+            String::from("prefund_addr"),
+            vec![
+                (String::from("ido_address_1"), FunderInfoResponse {
+                    available_funds: Uint128::from(1000u128),
+                    spent_funds: Uint128::zero(),
+                }),
+                (String::from("ido_address_2"), FunderInfoResponse {
+                    available_funds: Uint128::from(300u128),
+                    spent_funds: Uint128::zero(),
+                }),
+            ],
+        )],
+        vec![(
+            String::from("kyc_vault_address"),
+            vec![
+                (String::from("ido_address_1"), (true, true)),
+                (String::from("ido_address_2"), (false, true)),
+            ],
+        )],
+    );
+    deps.querier.with_bond_amounts(vec![(
+        String::from("staking_gateway_address"),
+        vec![(String::from("ido_address_1"), Uint128::from(100u128))],
+    )]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        prefund_address: String::from("prefund_addr"),
+        kyc_terms_vault_address: String::from("kyc_vault_address"),
+        ido_token: String::from("ido_token_address"),
+        ido_token_price: Uint128::from(50000u128),
+        end_date: 100000u64,
+        paused: false,
+        minimum_prefund: Uint128::from(500u128),
+        staking_gateway_address: String::from("staking_gateway_address"),
+        tiers: vec![],
+        allocation_per_weight: Uint128::zero(),
+        soft_cap: Uint128::zero(),
+        phases: vec![Phase {
+            name: String::from("whitelist"),
+            start_time: 1000,
+            end_time: 1999,
+            minimum_prefund: Some(Uint128::from(400u128)),
+            stakers_only: true,
+            max_participants: Some(10),
+        }],
+        hard_cap: Uint128::zero(),
+        vesting: None,
+        leave_penalty: Uint128::zero(),
+        treasury_address: String::from("treasury_address"),
+        terraswap_router: String::from("router0000"),
+        referral_share: Decimal::zero(),
+    };
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(100);
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // before the phase nobody can join
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Eligibility { address: String::from("ido_address_1"), block_time: None }).unwrap();
+    let eligibility: EligibilityResponse = from_binary(&res).unwrap();
+    assert!(!eligibility.is_eligible);
+    assert_eq!(
+        eligibility.checks.iter().filter(|check| !check.passed).map(|check| check.rule.clone()).collect::<Vec<EligibilityRule>>(),
+        vec![EligibilityRule::ActivePhase]
+    );
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Eligibility { address: String::from("ido_address_1"), block_time: Some(1500) }).unwrap();
+    let eligibility: EligibilityResponse = from_binary(&res).unwrap();
+    assert!(eligibility.is_eligible);
+
+    // every failed rule is reported at once, with the numbers compared
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Eligibility { address: String::from("ido_address_2"), block_time: Some(1500) }).unwrap();
+    let eligibility: EligibilityResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        eligibility,
+        EligibilityResponse {
+            address: String::from("ido_address_2"),
+            is_eligible: false,
+            checks: vec![
+                EligibilityCheck { rule: EligibilityRule::NotJoined, passed: true, required: None, actual: None },
+                EligibilityCheck { rule: EligibilityRule::NotPaused, passed: true, required: None, actual: None },
+                EligibilityCheck { rule: EligibilityRule::NotClosed, passed: true, required: None, actual: None },
+                EligibilityCheck { rule: EligibilityRule::NotCancelled, passed: true, required: None, actual: None },
+                EligibilityCheck { rule: EligibilityRule::BeforeSnapshot, passed: true, required: None, actual: None },
+                EligibilityCheck { rule: EligibilityRule::ActivePhase, passed: true, required: None, actual: None },
+                EligibilityCheck {
+                    rule: EligibilityRule::PhaseCapacity,
+                    passed: true,
+                    required: Some(Uint128::from(10u128)),
+                    actual: Some(Uint128::zero()),
+                },
+                EligibilityCheck {
+                    rule: EligibilityRule::Prefund,
+                    passed: false,
+                    required: Some(Uint128::from(400u128)),
+                    actual: Some(Uint128::from(300u128)),
+                },
+                EligibilityCheck { rule: EligibilityRule::Kyc, passed: true, required: None, actual: None },
+                EligibilityCheck { rule: EligibilityRule::TermsOfUse, passed: false, required: None, actual: None },
+                EligibilityCheck {
+                    rule: EligibilityRule::Staking,
+                    passed: false,
+                    required: Some(Uint128::from(1u128)),
+                    actual: Some(Uint128::zero()),
+                },
+            ],
+        }
+    );
+
+    env.block.time = Timestamp::from_seconds(1500);
+    execute(deps.as_mut(), env.clone(), mock_info("ido_address_1", &[]), ExecuteMsg::JoinIdo { referrer: None }).unwrap();
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Eligibility { address: String::from("ido_address_1"), block_time: None }).unwrap();
+    let eligibility: EligibilityResponse = from_binary(&res).unwrap();
+    assert!(!eligibility.is_eligible);
+    assert!(!eligibility.checks[0].passed);
+
+    // missing the soft cap at end_date is reported as cancelled
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: None,
+        snapshot_time: None,
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: Some(Uint128::from(10000u128)),
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+    let res = query(deps.as_ref(), env, QueryMsg::Eligibility { address: String::from("ido_address_2"), block_time: Some(100001) }).unwrap();
+    let eligibility: EligibilityResponse = from_binary(&res).unwrap();
+    assert!(!eligibility.checks.iter().find(|check| check.rule == EligibilityRule::NotCancelled).unwrap().passed);
+}
+
+#[test]
+fn migrate_legacy_config() {
+    let mut deps = mock_dependencies(&[], "some");
+
+    // config, state and participant exactly as stored by the baseline contract
+    let canonical = |address: &str| Binary::from(deps.api.addr_canonicalize(address).unwrap().as_slice()).to_base64();
+    let baseline_config = format!(
+        r#"{{"owner":"{}","pending_owner":null,"prefund_address":"{}","kyc_terms_vault_address":"{}","ido_token":"{}","ido_token_price":"2","end_date":1700000000,"paused":false,"snapshot_time":null,"minimum_prefund":"100"}}"#,
+        canonical("owner"),
+        canonical("prefund_addr"),
+        canonical("kyc_vault_address"),
+        canonical("ido_token_address"),
+    );
+    let user = deps.api.addr_canonicalize("user").unwrap();
+    let mut participant_key = to_length_prefixed(b"ido_participant");
+    participant_key.extend_from_slice(user.as_slice());
+    deps.storage.set(&to_length_prefixed(b"config"), baseline_config.as_bytes());
+    deps.storage.set(&to_length_prefixed(b"state"), br#"{"number_of_participants":1}"#);
+    deps.storage.set(&participant_key, br#"{"allocation":"150","is_joined":true}"#);
+
+    let legacy_migrate_msg = MigrateMsg {
+        staking_gateway_address: Some(String::from("staking_gateway")),
+        treasury_address: Some(String::from("treasury")),
+        terraswap_router: None,
+    };
+    let res = migrate(deps.as_mut(), mock_env(), legacy_migrate_msg);
+    match res {
+        Err(ContractError::LegacyConfigAddressMissing {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let legacy_migrate_msg = MigrateMsg {
+        staking_gateway_address: Some(String::from("staking_gateway")),
+        treasury_address: Some(String::from("treasury")),
+        terraswap_router: Some(String::from("router")),
+    };
+    let _res = migrate(deps.as_mut(), mock_env(), legacy_migrate_msg).unwrap();
+
+    let config: ConfigResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.owner, String::from("owner"));
+    assert_eq!(config.ido_token_price, Uint128::from(2u64));
+    assert_eq!(config.minimum_prefund, Uint128::from(100u64));
+    assert_eq!(config.staking_gateway_address, String::from("staking_gateway"));
+    assert_eq!(config.treasury_address, String::from("treasury"));
+    assert_eq!(config.terraswap_router, String::from("router"));
+    assert_eq!(config.tiers, vec![]);
+    assert_eq!(config.hard_cap, Uint128::zero());
+
+    let state: StateResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(state.number_of_participants, 1);
+    assert!(!state.is_settled);
+
+    let allocation: AllocationInfo = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Allocation {
+        address: String::from("user"),
+    }).unwrap()).unwrap();
+    assert_eq!(allocation.allocation, Uint128::from(150u64));
+    assert_eq!(allocation.claimed_amount, Uint128::zero());
+
+    // migrating again keeps the migrated config
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        prefund_address: None,
+        kyc_terms_vault_address: None,
+        ido_token: None,
+        ido_token_price: None,
+        end_date: None,
+        paused: Some(true),
+        snapshot_time: None,
+        minimum_prefund: None,
+        staking_gateway_address: None,
+        tiers: None,
+        allocation_per_weight: None,
+        soft_cap: None,
+        phases: None,
+        hard_cap: None,
+        vesting: None,
+        leave_penalty: None,
+        treasury_address: None,
+        terraswap_router: None,
+        referral_share: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    let _res = migrate(deps.as_mut(), mock_env(), MigrateMsg {
+        staking_gateway_address: None,
+        treasury_address: None,
+        terraswap_router: None,
+    }).unwrap();
+    let config: ConfigResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert!(config.paused);
+}
@@ -1,5 +1,9 @@
-use cosmwasm_std::{ Deps, MessageInfo, Response};
-use crate::state::read_config;
+use cosmwasm_std::{ Deps, MessageInfo, Response, Uint128, Decimal};
+use sha2::{Digest, Sha256};
+use starterra_token::ido::{ParticipantResponse, Tier, Phase, VestingSchedule};
+use terraswap::asset::AssetInfo;
+use terraswap::router::SwapOperation;
+use crate::state::{read_config, Config, State};
 use crate::errors::ContractError;
 
 pub fn assert_owner_privilege(
@@ -12,3 +16,147 @@ pub fn assert_owner_privilege(
 
     Ok(Response::default())
 }
+
+// ido_token_price is quoted in uusd per one whole ido token
+pub const IDO_TOKEN_UNIT: u128 = 1_000_000;
+
+pub fn compute_ido_token_amount(
+    allocation: Uint128,
+    ido_token_price: Uint128,
+) -> Result<Uint128, ContractError> {
+    if ido_token_price.is_zero() {
+        return Err(ContractError::IdoTokenPriceZero {});
+    }
+
+    Ok(allocation.multiply_ratio(IDO_TOKEN_UNIT, ido_token_price))
+}
+
+pub fn assert_tiers(tiers: &[Tier]) -> Result<(), ContractError> {
+    if tiers.windows(2).any(|pair| pair[0].min_bond >= pair[1].min_bond) {
+        return Err(ContractError::InvalidTiers {});
+    }
+
+    Ok(())
+}
+
+pub fn assert_phases(phases: &[Phase], end_date: u64) -> Result<(), ContractError> {
+    if phases.iter().any(|phase| phase.start_time > phase.end_time || phase.end_time > end_date)
+        || phases.windows(2).any(|pair| pair[0].end_time >= pair[1].start_time) {
+        return Err(ContractError::InvalidPhases {});
+    }
+
+    Ok(())
+}
+
+pub fn find_phase(phases: &[Phase], block_time: u64) -> Option<(u32, &Phase)> {
+    phases.iter()
+        .enumerate()
+        .find(|(_, phase)| phase.start_time <= block_time && block_time <= phase.end_time)
+        .map(|(index, phase)| (index as u32, phase))
+}
+
+pub fn time_to_next_phase(phases: &[Phase], block_time: u64) -> Option<u64> {
+    phases.iter()
+        .find(|phase| phase.start_time > block_time)
+        .map(|phase| phase.start_time - block_time)
+}
+
+// tiers are sorted ascending, so the last matching one is the highest
+pub fn find_tier(tiers: &[Tier], bond_amount: Uint128) -> Option<&Tier> {
+    tiers.iter().rev().find(|tier| tier.min_bond <= bond_amount)
+}
+
+pub fn compute_allocation(
+    config: &Config,
+    participant: &ParticipantResponse,
+    available_funds: Uint128,
+) -> Uint128 {
+    match participant.tier {
+        Some(_) => {
+            let tier_allocation = config.allocation_per_weight * Uint128::from(participant.weight);
+            std::cmp::min(available_funds, tier_allocation)
+        }
+        None => available_funds,
+    }
+}
+
+pub fn assert_vesting_schedule(vesting: &Option<VestingSchedule>) -> Result<(), ContractError> {
+    match vesting {
+        Some(vesting) if vesting.tge_unlock > Decimal::one() => Err(ContractError::InvalidVestingSchedule {}),
+        _ => Ok(()),
+    }
+}
+
+pub fn assert_referral_share(referral_share: Decimal) -> Result<(), ContractError> {
+    if referral_share > Decimal::one() {
+        return Err(ContractError::InvalidReferralShare {});
+    }
+
+    Ok(())
+}
+
+pub fn compute_unlocked_amount(
+    vesting: &Option<VestingSchedule>,
+    amount: Uint128,
+    block_time: u64,
+) -> Uint128 {
+    let vesting = match vesting {
+        Some(vesting) => vesting,
+        None => return amount,
+    };
+    if block_time < vesting.tge_time {
+        return Uint128::zero();
+    }
+
+    let tge_amount = amount * vesting.tge_unlock;
+    let linear_start = vesting.tge_time + vesting.cliff;
+    if block_time < linear_start {
+        return tge_amount;
+    }
+    if block_time >= linear_start + vesting.duration {
+        return amount;
+    }
+
+    tge_amount + (amount - tge_amount).multiply_ratio(block_time - linear_start, vesting.duration)
+}
+
+// Missing the soft cap at end_date cancels the sale without any transaction
+pub fn is_cancelled(config: &Config, state: &State, block_time: u64) -> bool {
+    state.is_cancelled || (block_time > config.end_date && state.total_committed < config.soft_cap)
+}
+
+pub fn ust_asset_info() -> AssetInfo {
+    AssetInfo::NativeToken { denom: String::from("uusd") }
+}
+
+// the router has to swap from the sent asset and end up in uusd, uusd itself is deposited without a swap
+pub fn assert_swap_operations(offer_asset_info: &AssetInfo, operations: &[SwapOperation]) -> Result<(), ContractError> {
+    if offer_asset_info.equal(&ust_asset_info()) {
+        return Err(ContractError::InvalidSwapOperations {});
+    }
+    let (first, last) = match (operations.first(), operations.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Err(ContractError::InvalidSwapOperations {}),
+    };
+    let first_offer_asset_info = match first {
+        SwapOperation::NativeSwap { offer_denom, .. } => AssetInfo::NativeToken { denom: offer_denom.clone() },
+        SwapOperation::TerraSwap { offer_asset_info, .. } => offer_asset_info.clone(),
+    };
+    if !first_offer_asset_info.equal(offer_asset_info) || !last.get_target_asset_info().equal(&ust_asset_info()) {
+        return Err(ContractError::InvalidSwapOperations {});
+    }
+
+    Ok(())
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+// First 8 bytes of sha256(seed + address), reproducible off-chain with any sha256 tool
+pub fn lottery_ticket(seed: &str, address: &str) -> u64 {
+    let hash = Sha256::digest([seed.as_bytes(), address.as_bytes()].concat().as_slice());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[0..8]);
+    u64::from_be_bytes(bytes)
+}
@@ -3,7 +3,7 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use starterra_token::kyc_vault::{QueryMsg, IsVerifiedResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, IsAcceptedVerifiedResponse, IsAcceptedResponse};
+use starterra_token::kyc_vault::{QueryMsg, IsVerifiedResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, IsAcceptedVerifiedResponse, IsAcceptedResponse, KycRecordResponse, IsVerifiedAtLevelResponse, ProvidersResponse, TermsOfUseResponse, VerifiedAddressesResponse, AcceptedAddressesResponse, StatsResponse, BlockedAddressesResponse, AddressLinksResponse};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -16,5 +16,14 @@ fn main() {
     export_schema(&schema_for!(IsVerifiedResponse), &out_dir);
     export_schema(&schema_for!(IsAcceptedResponse), &out_dir);
     export_schema(&schema_for!(IsAcceptedVerifiedResponse), &out_dir);
+    export_schema(&schema_for!(KycRecordResponse), &out_dir);
+    export_schema(&schema_for!(IsVerifiedAtLevelResponse), &out_dir);
+    export_schema(&schema_for!(ProvidersResponse), &out_dir);
+    export_schema(&schema_for!(TermsOfUseResponse), &out_dir);
+    export_schema(&schema_for!(VerifiedAddressesResponse), &out_dir);
+    export_schema(&schema_for!(AcceptedAddressesResponse), &out_dir);
+    export_schema(&schema_for!(StatsResponse), &out_dir);
+    export_schema(&schema_for!(BlockedAddressesResponse), &out_dir);
+    export_schema(&schema_for!(AddressLinksResponse), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
 }
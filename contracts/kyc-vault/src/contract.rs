@@ -3,16 +3,17 @@ use cosmwasm_std::entry_point;
 use cosmwasm_std:: {Env, Response, StdResult, Binary, to_binary, DepsMut, MessageInfo, Deps};
 
 use starterra_token::kyc_vault::{MigrateMsg, QueryMsg, InstantiateMsg, ExecuteMsg};
-use crate::state::{store_config, Config};
-use crate::execute::{register_kyc_account, register_kyc_accounts, accept_terms_of_use, update_config, accept_ownership};
-use crate::tools::{assert_kyc_provider_privilege};
-use crate::queries::{query_config, query_verified, query_accepted, query_accepted_verified};
+use crate::state::{store_config, store_provider, read_legacy_config, Config, Provider};
+use starterra_token::common::convert_human_to_raw;
+use crate::execute::{migrate_legacy_entries, submit_attestation, link_address, block_addresses, unblock_addresses, register_kyc_account, register_kyc_accounts, unregister_kyc_account, unregister_kyc_accounts, accept_terms_of_use, publish_terms_of_use, update_config, accept_ownership, add_kyc_provider, remove_kyc_provider};
+use crate::tools::{assert_kyc_provider_privilege, assert_owner_or_kyc_provider_privilege, assert_owner_privilege, KycMetadata};
+use crate::queries::{query_config, query_providers, query_verified_addresses, query_accepted_addresses, query_blocked_addresses, query_address_links, query_stats, query_terms_of_use, query_verified, query_accepted, query_accepted_verified, query_kyc_record, query_verified_at_level};
 use crate::errors::ContractError;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -20,9 +21,15 @@ pub fn instantiate(
        deps.storage,
         &Config {
             owner: deps.api.addr_canonicalize(&msg.owner)?,
-            kyc_provider_address: deps.api.addr_canonicalize(&msg.kyc_provider_address)?,
+            default_validity: msg.default_validity,
         },
     )?;
+    for kyc_provider in convert_human_to_raw(deps.as_ref(), &msg.kyc_providers)? {
+        store_provider(deps.storage, &kyc_provider, &Provider {
+            added_at: env.block.time.seconds(),
+            pubkey: None,
+        })?;
+    }
 
     Ok(Response::default())
 }
@@ -30,31 +37,84 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg.clone() {
-        ExecuteMsg::AcceptTermsOfUse {} => accept_terms_of_use(deps, info.clone()),
+        ExecuteMsg::AcceptTermsOfUse { version } => accept_terms_of_use(deps, env, info, version),
         ExecuteMsg::UpdateConfig {
             owner,
-            kyc_provider_address,
-        } => update_config(deps, info, owner, kyc_provider_address),
+            default_validity,
+        } => update_config(deps, info, owner, default_validity),
         ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::PublishTermsOfUse {
+            content_hash,
+            effective_from,
+        } => {
+            assert_owner_privilege(deps.as_ref(), &info)?;
+            publish_terms_of_use(deps, env, content_hash, effective_from)
+        }
+        ExecuteMsg::SubmitAttestation {
+            expiry,
+            level,
+            signature,
+        } => submit_attestation(deps, env, info, expiry, level, signature),
+        ExecuteMsg::BlockAddresses { addresses } => {
+            assert_owner_or_kyc_provider_privilege(deps.as_ref(), &info)?;
+            block_addresses(deps, env, info, &addresses)
+        }
+        ExecuteMsg::UnblockAddresses { addresses } => {
+            assert_owner_or_kyc_provider_privilege(deps.as_ref(), &info)?;
+            unblock_addresses(deps, info, &addresses)
+        }
+        ExecuteMsg::MigrateLegacyEntries { limit } => {
+            assert_owner_privilege(deps.as_ref(), &info)?;
+            migrate_legacy_entries(deps, limit)
+        }
+        ExecuteMsg::AddProvider { address, pubkey } => {
+            assert_owner_privilege(deps.as_ref(), &info)?;
+            add_kyc_provider(deps, env, address, pubkey)
+        }
+        ExecuteMsg::RemoveProvider {
+            address,
+            invalidate_verifications,
+        } => {
+            assert_owner_privilege(deps.as_ref(), &info)?;
+            remove_kyc_provider(deps, env, address, invalidate_verifications)
+        }
         _ => {
-            assert_kyc_provider_privilege(deps.as_ref(), info)?;
+            assert_kyc_provider_privilege(deps.as_ref(), info.clone())?;
             match msg {
-                ExecuteMsg::RegisterAddress { address } => {
-                    register_kyc_account(deps, &address, true)
-                }
-                ExecuteMsg::RegisterAddresses { addresses } => {
-                    register_kyc_accounts(deps, &addresses, true)
+                ExecuteMsg::RegisterAddress {
+                    address,
+                    expires_at,
+                    level,
+                    country,
+                    reference_hashes,
+                } => register_kyc_account(deps, env, info, &address, expires_at, KycMetadata {
+                    level,
+                    country,
+                    reference_hashes,
+                }),
+                ExecuteMsg::RegisterAddresses {
+                    addresses,
+                    expires_at,
+                    level,
+                    country,
+                } => register_kyc_accounts(deps, env, info, &addresses, expires_at, KycMetadata {
+                    level,
+                    country,
+                    reference_hashes: None,
+                }),
+                ExecuteMsg::LinkAddress { old, new } => {
+                    link_address(deps, env, info, old, new)
                 }
                 ExecuteMsg::UnregisterAddress { address } => {
-                    register_kyc_account(deps, &address, false)
+                    unregister_kyc_account(deps, &address)
                 }
                 ExecuteMsg::UnregisterAddresses { addresses } => {
-                    register_kyc_accounts(deps, &addresses, false)
+                    unregister_kyc_accounts(deps, &addresses)
                 }
                 _ => panic!("DO NOT ENTER HERE"),
             }
@@ -66,25 +126,67 @@ pub fn execute(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(
     deps: Deps,
-    _env: Env,
+    env: Env,
     msg: QueryMsg,
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Providers {} => to_binary(&query_providers(deps)?),
+        QueryMsg::VerifiedAddresses {
+            start_after,
+            limit,
+            order_by,
+        } => to_binary(&query_verified_addresses(deps, env, start_after, limit, order_by)?),
+        QueryMsg::AcceptedAddresses {
+            start_after,
+            limit,
+            order_by,
+        } => to_binary(&query_accepted_addresses(deps, start_after, limit, order_by)?),
+        QueryMsg::BlockedAddresses {
+            start_after,
+            limit,
+            order_by,
+        } => to_binary(&query_blocked_addresses(deps, start_after, limit, order_by)?),
+        QueryMsg::AddressLinks { address } => {
+            to_binary(&query_address_links(deps, address)?)
+        }
+        QueryMsg::Stats {} => to_binary(&query_stats(deps)?),
         QueryMsg::IsVerified { address } => {
-            to_binary(&query_verified(deps, address)?)
+            to_binary(&query_verified(deps, env, address)?)
         }
         QueryMsg::IsAccepted { address } => {
-            to_binary(&query_accepted(deps, address)?)
+            to_binary(&query_accepted(deps, env, address)?)
+        }
+        QueryMsg::TermsOfUse { version } => {
+            to_binary(&query_terms_of_use(deps, env, version)?)
         }
         QueryMsg::IsAcceptedVerified { address } => {
-            to_binary(&query_accepted_verified(deps, address)?)
+            to_binary(&query_accepted_verified(deps, env, address)?)
         }
+        QueryMsg::KycRecord { address } => {
+            to_binary(&query_kyc_record(deps, env, address)?)
+        }
+        QueryMsg::IsVerifiedAtLevel {
+            address,
+            min_level,
+            blocked_countries,
+        } => to_binary(&query_verified_at_level(deps, env, address, min_level, blocked_countries)?),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    if let Some(legacy_config) = read_legacy_config(deps.storage) {
+        store_config(deps.storage, &Config {
+            owner: legacy_config.owner,
+            default_validity: 0,
+        })?;
+        store_provider(deps.storage, &legacy_config.kyc_provider_address, &Provider {
+            added_at: env.block.time.seconds(),
+            pubkey: None,
+        })?;
+    }
+
     Ok(Response::default())
 }
 
@@ -12,6 +12,51 @@ pub enum ContractError {
     #[error("Pending owner missing")]
     PendingOwnerMissing {},
 
-    #[error("You already accepted Terms of Use")]
+    #[error("You already accepted this version of Terms of Use")]
     TouAlreadyAccepted {},
+
+    #[error("Verification expiry has to be in the future")]
+    ExpiryInThePast {},
+
+    #[error("Country has to be an ISO 3166-1 alpha-2 code")]
+    InvalidCountryCode {},
+
+    #[error("Reference hash has to be a sha256 hex digest")]
+    InvalidReferenceHash {},
+
+    #[error("Provider is already registered")]
+    ProviderAlreadyRegistered {},
+
+    #[error("Provider is not registered")]
+    ProviderNotRegistered {},
+
+    #[error("Terms of Use content hash has to be a sha256 hex digest")]
+    InvalidContentHash {},
+
+    #[error("Terms of Use have to take effect after the latest published version")]
+    InvalidEffectiveDate {},
+
+    #[error("Terms of Use version is not published or no longer in effect")]
+    InvalidTermsOfUseVersion {},
+
+    #[error("Attestation is not signed by a registered provider")]
+    InvalidAttestation {},
+
+    #[error("Attestation was already used")]
+    AttestationAlreadyUsed {},
+
+    #[error("Address is blocked")]
+    AddressBlocked {},
+
+    #[error("Address is not verified")]
+    AddressNotVerified {},
+
+    #[error("Address is already verified")]
+    AddressAlreadyVerified {},
+
+    #[error("Address was linked to another wallet and needs to be verified by a provider")]
+    AddressLinked {},
+
+    #[error("Address is already part of a link")]
+    AddressAlreadyLinked {},
 }
@@ -1,73 +1,402 @@
-use cosmwasm_std:: {DepsMut, Response, MessageInfo};
-use crate::state::{store_kyc_address, read_tou_address, store_tou_address, read_config, store_config, read_pending_owner, Config, remove_pending_owner, store_pending_owner};
+use cosmwasm_std:: {DepsMut, Env, Response, MessageInfo, Storage, CanonicalAddr, StdResult, Binary};
+use crate::state::{read_legacy_kyc_addresses, read_legacy_tou_addresses, remove_legacy_kyc_address, remove_legacy_tou_address, is_kyc_record_stored, is_tou_acceptance_stored, remove_accepted_tou_version, store_address_link, read_address_link_to, read_address_link_from, AddressLink, is_address_blocked, read_blocked_address, store_blocked_address, remove_blocked_address, BlockedAddress, read_providers, is_attestation_used, store_used_attestation, read_kyc_record, read_stats, store_stats, KycRecord, read_provider, store_provider, remove_provider, store_provider_invalidation, Provider, store_kyc_record, remove_kyc_record, read_accepted_tou_version, store_accepted_tou_version, read_current_tou_version, read_latest_terms_of_use, read_terms_of_use, store_terms_of_use, TermsOfUse, read_config, store_config, read_pending_owner, Config, remove_pending_owner, store_pending_owner};
 use starterra_token::common::convert_human_to_raw;
 use crate::errors::ContractError;
+use crate::tools::{new_kyc_record, assert_owner_privilege, is_sha256_hex, attestation_message_hash, KycMetadata};
+use starterra_token::kyc_vault::KycLevel;
+
+const DEFAULT_MIGRATION_LIMIT: u32 = 100;
+const MAX_MIGRATION_LIMIT: u32 = 500;
 
 pub fn register_kyc_account(
     deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     address: &String,
-    is_registering: bool,
+    expires_at: Option<u64>,
+    metadata: KycMetadata,
 ) -> Result<Response, ContractError> {
+    let provider = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let record = new_kyc_record(deps.storage, &env, provider, expires_at, metadata)?;
     let kyc_address = deps.api.addr_canonicalize(address)?;
-    store_kyc_address(
+    save_kyc_record(
         deps.storage,
         &kyc_address,
-        is_registering,
+        &record,
     )?;
 
-    let log_action_msg = if is_registering { "register_kyc_address" } else { "unregister_kyc_address" };
-    let log_msg = if is_registering { "registered_kyc_address" } else { "unregistered_kyc_address" };
-
     Ok(Response::new()
-        .add_attribute("action", log_action_msg)
-        .add_attribute(log_msg, address)
+        .add_attribute("action", "register_kyc_address")
+        .add_attribute("registered_kyc_address", address)
+        .add_attribute("provider", info.sender)
     )
 }
 
 pub fn register_kyc_accounts(
     deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     addresses: &Vec<String>,
-    is_registering: bool,
+    expires_at: Option<u64>,
+    metadata: KycMetadata,
 ) -> Result<Response, ContractError> {
+    let provider = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let record = new_kyc_record(deps.storage, &env, provider, expires_at, metadata)?;
     let kyc_addresses = convert_human_to_raw(deps.as_ref(), addresses)?;
     for kyc_address in kyc_addresses {
-        store_kyc_address(
+        save_kyc_record(
             deps.storage,
             &kyc_address,
-            is_registering,
+            &record,
         )?;
     }
 
-    let log_action_msg = if is_registering { "register_kyc_addresses" } else { "unregister_kyc_addresses" };
-    let log_msg = if is_registering { "registered_kyc_addresses" } else { "unregistered_kyc_addresses" };
     let log_addresses: Vec<String> = addresses.into_iter().map(|x| x.to_string()).collect();
 
     Ok(Response::new()
-        .add_attribute("action", log_action_msg)
-        .add_attribute(log_msg, log_addresses.join(","))
+        .add_attribute("action", "register_kyc_addresses")
+        .add_attribute("registered_kyc_addresses", log_addresses.join(","))
+        .add_attribute("provider", info.sender)
+    )
+}
+
+pub fn submit_attestation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    expiry: u64,
+    level: KycLevel,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let kyc_address = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if is_address_blocked(deps.storage, &kyc_address)? {
+        return Err(ContractError::AddressBlocked {});
+    }
+    if read_address_link_to(deps.storage, &kyc_address)?.is_some() {
+        return Err(ContractError::AddressLinked {});
+    }
+    let message_hash = attestation_message_hash(&env, info.sender.as_str(), level, expiry);
+    if is_attestation_used(deps.storage, &message_hash)? {
+        return Err(ContractError::AttestationAlreadyUsed {});
+    }
+
+    let provider = read_providers(deps.storage)?
+        .into_iter()
+        .find(|(_, provider)| match &provider.pubkey {
+            Some(pubkey) => deps.api.secp256k1_verify(&message_hash, &signature, pubkey).unwrap_or(false),
+            None => false,
+        })
+        .map(|(address, _)| address)
+        .ok_or(ContractError::InvalidAttestation {})?;
+    let provider_address = deps.api.addr_humanize(&provider)?;
+    let record = new_kyc_record(deps.storage, &env, provider, Some(expiry), KycMetadata {
+        level: Some(level),
+        country: None,
+        reference_hashes: None,
+    })?;
+    save_kyc_record(deps.storage, &kyc_address, &record)?;
+    store_used_attestation(deps.storage, &message_hash)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "submit_attestation")
+        .add_attribute("registered_kyc_address", info.sender)
+        .add_attribute("provider", provider_address)
+    )
+}
+
+pub fn block_addresses(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addresses: &Vec<String>,
+) -> Result<Response, ContractError> {
+    let blocked_address = BlockedAddress {
+        blocked_at: env.block.time.seconds(),
+        blocked_by: deps.api.addr_canonicalize(info.sender.as_str())?,
+    };
+    for address in convert_human_to_raw(deps.as_ref(), addresses)? {
+        // an existing block keeps the sender who added it
+        if !is_address_blocked(deps.storage, &address)? {
+            store_blocked_address(deps.storage, &address, &blocked_address)?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "block_addresses")
+        .add_attribute("blocked_addresses", addresses.join(","))
+        .add_attribute("sender", info.sender)
+    )
+}
+
+pub fn unblock_addresses(
+    deps: DepsMut,
+    info: MessageInfo,
+    addresses: &Vec<String>,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let is_owner = read_config(deps.storage)?.owner == sender;
+    for address in convert_human_to_raw(deps.as_ref(), addresses)? {
+        // only the owner or the sender who added the block can lift it
+        if let Some(blocked_address) = read_blocked_address(deps.storage, &address)? {
+            if !is_owner && blocked_address.blocked_by != sender {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+        remove_blocked_address(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "unblock_addresses")
+        .add_attribute("unblocked_addresses", addresses.join(","))
+        .add_attribute("sender", info.sender)
+    )
+}
+
+pub fn unregister_kyc_account(
+    deps: DepsMut,
+    address: &String,
+) -> Result<Response, ContractError> {
+    let kyc_address = deps.api.addr_canonicalize(address)?;
+    delete_kyc_record(deps.storage, &kyc_address)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "unregister_kyc_address")
+        .add_attribute("unregistered_kyc_address", address)
+    )
+}
+
+pub fn unregister_kyc_accounts(
+    deps: DepsMut,
+    addresses: &Vec<String>,
+) -> Result<Response, ContractError> {
+    let kyc_addresses = convert_human_to_raw(deps.as_ref(), addresses)?;
+    for kyc_address in kyc_addresses {
+        delete_kyc_record(deps.storage, &kyc_address)?;
+    }
+
+    let log_addresses: Vec<String> = addresses.into_iter().map(|x| x.to_string()).collect();
+
+    Ok(Response::new()
+        .add_attribute("action", "unregister_kyc_addresses")
+        .add_attribute("unregistered_kyc_addresses", log_addresses.join(","))
+    )
+}
+
+// keeps the stats counters in line with the stored records
+fn save_kyc_record(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+    record: &KycRecord,
+) -> StdResult<()> {
+    if !is_kyc_record_stored(storage, address)? {
+        let mut stats = read_stats(storage)?;
+        stats.total_verified += 1;
+        if is_tou_acceptance_stored(storage, address)? {
+            stats.total_verified_accepted += 1;
+        }
+        store_stats(storage, &stats)?;
+    }
+
+    store_kyc_record(storage, address, record)
+}
+
+fn delete_kyc_record(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+) -> StdResult<()> {
+    if is_kyc_record_stored(storage, address)? {
+        let mut stats = read_stats(storage)?;
+        stats.total_verified -= 1;
+        if is_tou_acceptance_stored(storage, address)? {
+            stats.total_verified_accepted -= 1;
+        }
+        store_stats(storage, &stats)?;
+    }
+
+    remove_kyc_record(storage, address);
+    Ok(())
+}
+
+fn save_accepted_tou_version(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+    version: u32,
+) -> StdResult<()> {
+    if !is_tou_acceptance_stored(storage, address)? {
+        let mut stats = read_stats(storage)?;
+        stats.total_accepted += 1;
+        if is_kyc_record_stored(storage, address)? {
+            stats.total_verified_accepted += 1;
+        }
+        store_stats(storage, &stats)?;
+    }
+
+    store_accepted_tou_version(storage, address, version)
+}
+
+fn delete_accepted_tou_version(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+) -> StdResult<()> {
+    if is_tou_acceptance_stored(storage, address)? {
+        let mut stats = read_stats(storage)?;
+        stats.total_accepted -= 1;
+        if is_kyc_record_stored(storage, address)? {
+            stats.total_verified_accepted -= 1;
+        }
+        store_stats(storage, &stats)?;
+    }
+
+    remove_accepted_tou_version(storage, address);
+    Ok(())
+}
+
+// Moves up to limit bool registrations and acceptances into records so they can be enumerated and counted,
+// reads already fall back to the legacy entries so the owner can spread this over several transactions
+pub fn migrate_legacy_entries(
+    deps: DepsMut,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_MIGRATION_LIMIT).min(MAX_MIGRATION_LIMIT) as usize;
+
+    let legacy_kyc_addresses = read_legacy_kyc_addresses(deps.storage, limit)?;
+    let mut migrated = legacy_kyc_addresses.len();
+    for (address, is_verified) in legacy_kyc_addresses {
+        if is_verified && !is_kyc_record_stored(deps.storage, &address)? {
+            save_kyc_record(deps.storage, &address, &KycRecord {
+                provider: None,
+                verified_at: 0,
+                expires_at: None,
+                level: KycLevel::Basic,
+                country: None,
+                reference_hashes: vec![],
+            })?;
+        }
+        remove_legacy_kyc_address(deps.storage, &address);
+    }
+
+    let legacy_tou_addresses = read_legacy_tou_addresses(deps.storage, limit - migrated)?;
+    migrated += legacy_tou_addresses.len();
+    for (address, is_accepted) in legacy_tou_addresses {
+        if is_accepted && !is_tou_acceptance_stored(deps.storage, &address)? {
+            save_accepted_tou_version(deps.storage, &address, 0)?;
+        }
+        remove_legacy_tou_address(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate_legacy_entries")
+        .add_attribute("migrated_entries", migrated.to_string())
+    )
+}
+
+pub fn link_address(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    old: String,
+    new: String,
+) -> Result<Response, ContractError> {
+    let old_address = deps.api.addr_canonicalize(&old)?;
+    let new_address = deps.api.addr_canonicalize(&new)?;
+    if is_address_blocked(deps.storage, &old_address)? || is_address_blocked(deps.storage, &new_address)? {
+        return Err(ContractError::AddressBlocked {});
+    }
+    let record = match read_kyc_record(deps.storage, &old_address)? {
+        Some(record) if old_address != new_address => record,
+        _ => return Err(ContractError::AddressNotVerified {}),
+    };
+    if read_kyc_record(deps.storage, &new_address)?.is_some() {
+        return Err(ContractError::AddressAlreadyVerified {});
+    }
+    // each address keeps a single link record in either direction
+    if read_address_link_to(deps.storage, &old_address)?.is_some() || read_address_link_from(deps.storage, &new_address)?.is_some() {
+        return Err(ContractError::AddressAlreadyLinked {});
+    }
+
+    let accepted_version = read_accepted_tou_version(deps.storage, &old_address)?;
+    delete_kyc_record(deps.storage, &old_address)?;
+    delete_accepted_tou_version(deps.storage, &old_address)?;
+    if let Some(accepted_version) = accepted_version {
+        let new_accepted_version = read_accepted_tou_version(deps.storage, &new_address)?;
+        if !matches!(new_accepted_version, Some(version) if version >= accepted_version) {
+            save_accepted_tou_version(deps.storage, &new_address, accepted_version)?;
+        }
+    }
+    save_kyc_record(deps.storage, &new_address, &record)?;
+    store_address_link(deps.storage, &AddressLink {
+        old_address,
+        new_address,
+        linked_at: env.block.time.seconds(),
+        linked_by: deps.api.addr_canonicalize(info.sender.as_str())?,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "link_address")
+        .add_attribute("old_address", old)
+        .add_attribute("new_address", new)
+        .add_attribute("provider", info.sender)
     )
 }
 
 pub fn accept_terms_of_use(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
+    version: u32,
 ) -> Result<Response, ContractError> {
-    let user_address = deps.api.addr_canonicalize(info.sender.as_str())?;
-    let account_accepted = read_tou_address(deps.storage, &user_address)?;
+    let current_version = read_current_tou_version(deps.storage, env.block.time.seconds())?;
+    if version < current_version || (version > 0 && read_terms_of_use(deps.storage, version)?.is_none()) {
+        return Err(ContractError::InvalidTermsOfUseVersion {});
+    }
 
-    if account_accepted {
+    let user_address = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if is_address_blocked(deps.storage, &user_address)? {
+        return Err(ContractError::AddressBlocked {});
+    }
+    let accepted_version = read_accepted_tou_version(deps.storage, &user_address)?;
+    if matches!(accepted_version, Some(accepted) if accepted >= version) {
         return Err(ContractError::TouAlreadyAccepted {});
     }
 
-    store_tou_address(
+    save_accepted_tou_version(
         deps.storage,
         &user_address,
-        true,
+        version,
     )?;
 
     Ok(Response::new()
         .add_attribute("action", "accept_terms_of_use")
         .add_attribute("address", info.sender.as_str())
+        .add_attribute("version", version.to_string())
+    )
+}
+
+pub fn publish_terms_of_use(
+    deps: DepsMut,
+    env: Env,
+    content_hash: String,
+    effective_from: u64,
+) -> Result<Response, ContractError> {
+    if !is_sha256_hex(&content_hash) {
+        return Err(ContractError::InvalidContentHash {});
+    }
+    let latest = read_latest_terms_of_use(deps.storage)?;
+    if effective_from < env.block.time.seconds()
+        || matches!(&latest, Some((_, terms_of_use)) if effective_from <= terms_of_use.effective_from) {
+        return Err(ContractError::InvalidEffectiveDate {});
+    }
+
+    let version = latest.map_or(1, |(version, _)| version + 1);
+    store_terms_of_use(deps.storage, version, &TermsOfUse {
+        content_hash,
+        effective_from,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "publish_terms_of_use")
+        .add_attribute("version", version.to_string())
+        .add_attribute("effective_from", effective_from.to_string())
     )
 }
 
@@ -75,19 +404,17 @@ pub fn update_config(
     deps: DepsMut,
     info: MessageInfo,
     owner: Option<String>,
-    kyc_provider_address: Option<String>,
+    default_validity: Option<u64>,
 ) -> Result<Response, ContractError> {
-    if read_config(deps.storage)?.owner != deps.api.addr_canonicalize(info.sender.as_str())? {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_owner_privilege(deps.as_ref(), &info)?;
     let mut config = read_config(deps.storage)?;
 
     if let Some(owner) = owner {
         store_pending_owner(deps.storage, &deps.api.addr_canonicalize(&owner)?)?;
     }
 
-    if let Some(kyc_provider_address) = kyc_provider_address {
-        config.kyc_provider_address = deps.api.addr_canonicalize(&kyc_provider_address)?;
+    if let Some(default_validity) = default_validity {
+        config.default_validity = default_validity;
     }
 
     store_config(deps.storage, &config)?;
@@ -97,6 +424,49 @@ pub fn update_config(
     )
 }
 
+pub fn add_kyc_provider(
+    deps: DepsMut,
+    env: Env,
+    address: String,
+    pubkey: Option<Binary>,
+) -> Result<Response, ContractError> {
+    let provider_address = deps.api.addr_canonicalize(&address)?;
+    if read_provider(deps.storage, &provider_address)?.is_some() {
+        return Err(ContractError::ProviderAlreadyRegistered {});
+    }
+    store_provider(deps.storage, &provider_address, &Provider {
+        added_at: env.block.time.seconds(),
+        pubkey,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_provider")
+        .add_attribute("provider", address)
+    )
+}
+
+pub fn remove_kyc_provider(
+    deps: DepsMut,
+    env: Env,
+    address: String,
+    invalidate_verifications: bool,
+) -> Result<Response, ContractError> {
+    let provider_address = deps.api.addr_canonicalize(&address)?;
+    if read_provider(deps.storage, &provider_address)?.is_none() {
+        return Err(ContractError::ProviderNotRegistered {});
+    }
+    remove_provider(deps.storage, &provider_address);
+    if invalidate_verifications {
+        store_provider_invalidation(deps.storage, &provider_address, env.block.time.seconds())?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_provider")
+        .add_attribute("provider", address)
+        .add_attribute("invalidate_verifications", invalidate_verifications.to_string())
+    )
+}
+
 pub fn accept_ownership(
     deps: DepsMut,
     info: MessageInfo,
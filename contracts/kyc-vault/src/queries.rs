@@ -1,6 +1,7 @@
-use cosmwasm_std::{StdResult, Deps};
-use crate::state::{read_config, read_kyc_address, read_tou_address};
-use starterra_token::kyc_vault::{ConfigResponse, IsVerifiedResponse, IsAcceptedVerifiedResponse, IsAcceptedResponse};
+use cosmwasm_std::{StdResult, StdError, Deps, Env, CanonicalAddr};
+use crate::state::{read_config, read_kyc_record, read_accepted_tou_version, read_current_tou_version, read_terms_of_use, read_providers, read_kyc_records, read_accepted_tou_versions, read_stats, read_blocked_addresses, is_address_blocked, is_kyc_record_invalidated, read_address_link_from, read_address_link_to, AddressLink, KycRecord};
+use starterra_token::kyc_vault::{ConfigResponse, IsVerifiedResponse, IsAcceptedVerifiedResponse, IsAcceptedResponse, KycRecordResponse, IsVerifiedAtLevelResponse, KycLevel, ProvidersResponse, ProviderResponse, TermsOfUseResponse, VerifiedAddressesResponse, AcceptedAddressesResponse, AcceptedAddressResponse, StatsResponse, BlockedAddressesResponse, BlockedAddressResponse, AddressLinksResponse, AddressLinkResponse};
+use starterra_token::common::OrderBy;
 
 pub fn query_config(
     deps: Deps,
@@ -8,46 +9,285 @@ pub fn query_config(
     let state = read_config(deps.storage)?;
     Ok(ConfigResponse {
         owner: deps.api.addr_humanize(&state.owner)?.into_string(),
-        kyc_provider_address: deps.api.addr_humanize(&state.kyc_provider_address)?.into_string(),
+        default_validity: state.default_validity,
     })
 }
 
+pub fn query_providers(
+    deps: Deps,
+) -> StdResult<ProvidersResponse> {
+    let providers = read_providers(deps.storage)?
+        .into_iter()
+        .map(|(address, provider)| Ok(ProviderResponse {
+            address: deps.api.addr_humanize(&address)?.into_string(),
+            added_at: provider.added_at,
+            pubkey: provider.pubkey,
+        }))
+        .collect::<StdResult<Vec<ProviderResponse>>>()?;
+
+    Ok(ProvidersResponse { providers })
+}
+
 pub fn query_verified(
     deps: Deps,
+    env: Env,
     address: String,
 ) -> StdResult<IsVerifiedResponse> {
     let user_raw = deps.api.addr_canonicalize(&address)?;
-    let is_verified = read_kyc_address(deps.storage, &user_raw).unwrap_or(false);
+    let (is_verified, expires_at) = verification_status(read_attributed_kyc_record(deps, &user_raw)?, &env);
     Ok(IsVerifiedResponse {
         address,
         is_verified,
+        expires_at,
     })
 }
 
 
 pub fn query_accepted(
     deps: Deps,
+    env: Env,
     address: String,
 ) -> StdResult<IsAcceptedResponse> {
     let user_raw = deps.api.addr_canonicalize(&address)?;
-    let is_accepted = read_tou_address(deps.storage, &user_raw).unwrap_or(false);
+    let accepted_version = read_accepted_tou_version(deps.storage, &user_raw)?;
+    let current_version = read_current_tou_version(deps.storage, env.block.time.seconds())?;
     Ok(IsAcceptedResponse {
+        is_accepted: !is_address_blocked(deps.storage, &user_raw)? && is_current_version_accepted(accepted_version, current_version),
         address,
-        is_accepted,
+        accepted_version,
+        current_version,
     })
 }
 
+pub fn query_terms_of_use(
+    deps: Deps,
+    env: Env,
+    version: Option<u32>,
+) -> StdResult<TermsOfUseResponse> {
+    let version = match version {
+        Some(version) => version,
+        None => read_current_tou_version(deps.storage, env.block.time.seconds())?,
+    };
+    match read_terms_of_use(deps.storage, version)? {
+        Some(terms_of_use) => Ok(TermsOfUseResponse {
+            version,
+            content_hash: terms_of_use.content_hash,
+            effective_from: terms_of_use.effective_from,
+        }),
+        None => Err(StdError::not_found("TermsOfUse")),
+    }
+}
+
 pub fn query_accepted_verified(
     deps: Deps,
+    env: Env,
     address: String,
 ) -> StdResult<IsAcceptedVerifiedResponse> {
     let user_raw = deps.api.addr_canonicalize(&address)?;
-    let is_accepted = read_tou_address(deps.storage, &user_raw).unwrap_or_else(|_| false);
-    let is_verified = read_kyc_address(deps.storage, &user_raw).unwrap_or_else(|_| false);
+    let is_blocked = is_address_blocked(deps.storage, &user_raw)?;
+    let is_accepted = !is_blocked && is_current_version_accepted(
+        read_accepted_tou_version(deps.storage, &user_raw)?,
+        read_current_tou_version(deps.storage, env.block.time.seconds())?,
+    );
+    let (is_verified, expires_at) = verification_status(read_attributed_kyc_record(deps, &user_raw)?, &env);
 
     Ok(IsAcceptedVerifiedResponse {
         address,
         is_accepted,
         is_verified,
+        expires_at,
+        is_blocked,
     })
 }
+
+pub fn query_kyc_record(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<KycRecordResponse> {
+    let user_raw = deps.api.addr_canonicalize(&address)?;
+    let record = read_kyc_record(deps.storage, &user_raw)?;
+    kyc_record_response(deps, &env, &user_raw, record)
+}
+
+pub fn query_verified_addresses(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<VerifiedAddressesResponse> {
+    let start_after = match start_after {
+        Some(start_after) => Some(deps.api.addr_canonicalize(&start_after)?),
+        None => None,
+    };
+    let records = read_kyc_records(deps.storage, start_after, limit, order_by)?
+        .into_iter()
+        .map(|(address, record)| kyc_record_response(deps, &env, &address, Some(record)))
+        .collect::<StdResult<Vec<KycRecordResponse>>>()?;
+
+    Ok(VerifiedAddressesResponse { records })
+}
+
+pub fn query_accepted_addresses(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<AcceptedAddressesResponse> {
+    let start_after = match start_after {
+        Some(start_after) => Some(deps.api.addr_canonicalize(&start_after)?),
+        None => None,
+    };
+    let addresses = read_accepted_tou_versions(deps.storage, start_after, limit, order_by)?
+        .into_iter()
+        .map(|(address, accepted_version)| Ok(AcceptedAddressResponse {
+            address: deps.api.addr_humanize(&address)?.into_string(),
+            accepted_version,
+        }))
+        .collect::<StdResult<Vec<AcceptedAddressResponse>>>()?;
+
+    Ok(AcceptedAddressesResponse { addresses })
+}
+
+pub fn query_blocked_addresses(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<BlockedAddressesResponse> {
+    let start_after = match start_after {
+        Some(start_after) => Some(deps.api.addr_canonicalize(&start_after)?),
+        None => None,
+    };
+    let addresses = read_blocked_addresses(deps.storage, start_after, limit, order_by)?
+        .into_iter()
+        .map(|(address, blocked_address)| Ok(BlockedAddressResponse {
+            address: deps.api.addr_humanize(&address)?.into_string(),
+            blocked_at: blocked_address.blocked_at,
+            blocked_by: deps.api.addr_humanize(&blocked_address.blocked_by)?.into_string(),
+        }))
+        .collect::<StdResult<Vec<BlockedAddressResponse>>>()?;
+
+    Ok(BlockedAddressesResponse { addresses })
+}
+
+pub fn query_address_links(
+    deps: Deps,
+    address: String,
+) -> StdResult<AddressLinksResponse> {
+    let user_raw = deps.api.addr_canonicalize(&address)?;
+    let linked_from = match read_address_link_from(deps.storage, &user_raw)? {
+        Some(link) => Some(address_link_response(deps, link)?),
+        None => None,
+    };
+    let linked_to = match read_address_link_to(deps.storage, &user_raw)? {
+        Some(link) => Some(address_link_response(deps, link)?),
+        None => None,
+    };
+
+    Ok(AddressLinksResponse {
+        address,
+        linked_from,
+        linked_to,
+    })
+}
+
+fn address_link_response(deps: Deps, link: AddressLink) -> StdResult<AddressLinkResponse> {
+    Ok(AddressLinkResponse {
+        old_address: deps.api.addr_humanize(&link.old_address)?.into_string(),
+        new_address: deps.api.addr_humanize(&link.new_address)?.into_string(),
+        linked_at: link.linked_at,
+        linked_by: deps.api.addr_humanize(&link.linked_by)?.into_string(),
+    })
+}
+
+pub fn query_stats(
+    deps: Deps,
+) -> StdResult<StatsResponse> {
+    let stats = read_stats(deps.storage)?;
+    Ok(StatsResponse {
+        total_verified: stats.total_verified,
+        total_accepted: stats.total_accepted,
+        total_verified_accepted: stats.total_verified_accepted,
+    })
+}
+
+fn kyc_record_response(
+    deps: Deps,
+    env: &Env,
+    user_raw: &CanonicalAddr,
+    record: Option<KycRecord>,
+) -> StdResult<KycRecordResponse> {
+    let address = deps.api.addr_humanize(user_raw)?.into_string();
+    let record = match record {
+        Some(record) => record,
+        None => return Ok(KycRecordResponse {
+            address,
+            is_verified: false,
+            provider: None,
+            level: None,
+            country: None,
+            reference_hashes: vec![],
+            verified_at: None,
+            expires_at: None,
+        }),
+    };
+    let is_revoked = is_kyc_record_invalidated(deps.storage, &record)? || is_address_blocked(deps.storage, user_raw)?;
+
+    Ok(KycRecordResponse {
+        address,
+        is_verified: !is_revoked && record.is_valid(env.block.time.seconds()),
+        provider: match &record.provider {
+            Some(provider) => Some(deps.api.addr_humanize(provider)?.into_string()),
+            None => None,
+        },
+        level: Some(record.level),
+        country: record.country,
+        reference_hashes: record.reference_hashes,
+        verified_at: Some(record.verified_at),
+        expires_at: record.expires_at,
+    })
+}
+
+pub fn query_verified_at_level(
+    deps: Deps,
+    env: Env,
+    address: String,
+    min_level: KycLevel,
+    blocked_countries: Vec<String>,
+) -> StdResult<IsVerifiedAtLevelResponse> {
+    let user_raw = deps.api.addr_canonicalize(&address)?;
+    let record = read_attributed_kyc_record(deps, &user_raw)?;
+    let is_verified = matches!(&record, Some((record, false)) if record.is_valid_at_level(env.block.time.seconds(), min_level, &blocked_countries));
+    let record = record.map(|(record, _)| record);
+
+    Ok(IsVerifiedAtLevelResponse {
+        address,
+        is_verified,
+        level: record.as_ref().map(|record| record.level),
+        country: record.and_then(|record| record.country),
+    })
+}
+
+// pairs the record with whether it was revoked by a provider invalidation or the blocklist
+fn read_attributed_kyc_record(deps: Deps, address: &CanonicalAddr) -> StdResult<Option<(KycRecord, bool)>> {
+    match read_kyc_record(deps.storage, address)? {
+        Some(record) => {
+            let is_revoked = is_kyc_record_invalidated(deps.storage, &record)? || is_address_blocked(deps.storage, address)?;
+            Ok(Some((record, is_revoked)))
+        }
+        None => Ok(None),
+    }
+}
+
+fn is_current_version_accepted(accepted_version: Option<u32>, current_version: u32) -> bool {
+    matches!(accepted_version, Some(accepted_version) if accepted_version >= current_version)
+}
+
+fn verification_status(record: Option<(KycRecord, bool)>, env: &Env) -> (bool, Option<u64>) {
+    match record {
+        Some((record, is_revoked)) => (!is_revoked && record.is_valid(env.block.time.seconds()), record.expires_at),
+        None => (false, None),
+    }
+}
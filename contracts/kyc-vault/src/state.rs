@@ -1,19 +1,33 @@
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, StdResult, Storage, };
+use cosmwasm_std::{Binary, CanonicalAddr, Order, StdResult, Storage};
 use cosmwasm_storage::{singleton, singleton_read,  Bucket, ReadonlyBucket, Singleton};
+use starterra_token::kyc_vault::KycLevel;
+use starterra_token::common::OrderBy;
 
 static KEY_CONFIG: &[u8] = b"config";
 static KEY_PENDING_OWNER: &[u8] = b"pending_owner";
+static KEY_STATS: &[u8] = b"stats";
 
 static PREFIX_KEY_KYC_ADDRESS: &[u8] = b"kyc_address";
+static PREFIX_KEY_KYC_RECORD: &[u8] = b"kyc_record";
 static PREFIX_KEY_TOU_ADDRESS: &[u8] = b"tou_address";
+static PREFIX_KEY_TOU_ACCEPTANCE: &[u8] = b"tou_acceptance";
+static PREFIX_KEY_TOU_VERSION: &[u8] = b"tou_version";
+static PREFIX_KEY_PROVIDER: &[u8] = b"provider";
+static PREFIX_KEY_PROVIDER_INVALIDATION: &[u8] = b"provider_invalidation";
+static PREFIX_KEY_ATTESTATION: &[u8] = b"attestation";
+static PREFIX_KEY_BLOCKED_ADDRESS: &[u8] = b"blocked_address";
+static PREFIX_KEY_LINK_FROM: &[u8] = b"link_from";
+static PREFIX_KEY_LINK_TO: &[u8] = b"link_to";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: CanonicalAddr,
-    pub kyc_provider_address: CanonicalAddr,
+    // zero keeps verifications valid forever, which is also what configs stored before expiry get
+    #[serde(default)]
+    pub default_validity: u64,
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -24,41 +38,400 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     singleton_read(storage, KEY_CONFIG).load()
 }
 
-pub fn store_kyc_address(
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct Stats {
+    pub total_verified: u64,
+    pub total_accepted: u64,
+    pub total_verified_accepted: u64,
+}
+
+pub fn store_stats(storage: &mut dyn Storage, stats: &Stats) -> StdResult<()> {
+    singleton(storage, KEY_STATS).save(stats)
+}
+
+pub fn read_stats(storage: &dyn Storage) -> StdResult<Stats> {
+    Ok(singleton_read(storage, KEY_STATS).may_load()?.unwrap_or_default())
+}
+
+// the config stored before the provider registry existed, with its single provider
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub owner: CanonicalAddr,
+    pub kyc_provider_address: CanonicalAddr,
+}
+
+pub fn read_legacy_config(storage: &dyn Storage) -> Option<LegacyConfig> {
+    singleton_read::<LegacyConfig>(storage, KEY_CONFIG).load().ok()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Provider {
+    pub added_at: u64,
+    #[serde(default)]
+    pub pubkey: Option<Binary>,
+}
+
+pub fn store_provider(
     storage: &mut dyn Storage,
     address: &CanonicalAddr,
-    is_registering: bool,
+    provider: &Provider,
 ) -> StdResult<()> {
-    Ok(Bucket::<bool>::new(storage, PREFIX_KEY_KYC_ADDRESS)
-        .save(address, &is_registering)?)
+    Bucket::<Provider>::new(storage, PREFIX_KEY_PROVIDER).save(address, provider)
 }
 
-pub fn read_kyc_address(
+pub fn remove_provider(storage: &mut dyn Storage, address: &CanonicalAddr) {
+    Bucket::<Provider>::new(storage, PREFIX_KEY_PROVIDER).remove(address);
+}
+
+pub fn read_provider(
     storage: &dyn Storage,
     address: &CanonicalAddr,
+) -> StdResult<Option<Provider>> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_PROVIDER).may_load(address)
+}
+
+pub fn read_providers(storage: &dyn Storage) -> StdResult<Vec<(CanonicalAddr, Provider)>> {
+    ReadonlyBucket::<Provider>::new(storage, PREFIX_KEY_PROVIDER)
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (k, provider) = item?;
+            Ok((CanonicalAddr::from(k), provider))
+        })
+        .collect()
+}
+
+// attestations are keyed by the hash of the signed message, so each one is used once
+pub fn store_used_attestation(storage: &mut dyn Storage, message_hash: &[u8]) -> StdResult<()> {
+    Bucket::<bool>::new(storage, PREFIX_KEY_ATTESTATION).save(message_hash, &true)
+}
+
+pub fn is_attestation_used(storage: &dyn Storage, message_hash: &[u8]) -> StdResult<bool> {
+    Ok(ReadonlyBucket::<bool>::new(storage, PREFIX_KEY_ATTESTATION).may_load(message_hash)?.is_some())
+}
+
+// verifications written by the provider up to this time are no longer valid
+pub fn store_provider_invalidation(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+    invalidated_at: u64,
+) -> StdResult<()> {
+    Bucket::<u64>::new(storage, PREFIX_KEY_PROVIDER_INVALIDATION).save(address, &invalidated_at)
+}
+
+pub fn is_kyc_record_invalidated(
+    storage: &dyn Storage,
+    record: &KycRecord,
 ) -> StdResult<bool> {
-    Ok(ReadonlyBucket::new(storage, PREFIX_KEY_KYC_ADDRESS).load(address)?)
+    let provider = match &record.provider {
+        Some(provider) => provider,
+        None => return Ok(false),
+    };
+    let invalidated_at = ReadonlyBucket::<u64>::new(storage, PREFIX_KEY_PROVIDER_INVALIDATION).may_load(provider)?;
+
+    Ok(matches!(invalidated_at, Some(invalidated_at) if record.verified_at <= invalidated_at))
 }
 
-pub fn store_tou_address(
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct KycRecord {
+    #[serde(default)]
+    pub provider: Option<CanonicalAddr>,
+    pub verified_at: u64,
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub level: KycLevel,
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub reference_hashes: Vec<String>,
+}
+
+impl KycRecord {
+    pub fn is_valid(&self, block_time: u64) -> bool {
+        !matches!(self.expires_at, Some(expires_at) if expires_at <= block_time)
+    }
+
+    pub fn is_valid_at_level(&self, block_time: u64, min_level: KycLevel, blocked_countries: &[String]) -> bool {
+        let is_blocked = !blocked_countries.is_empty() && match &self.country {
+            Some(country) => blocked_countries.iter().any(|blocked| blocked.eq_ignore_ascii_case(country)),
+            None => true,
+        };
+
+        self.is_valid(block_time) && self.level >= min_level && !is_blocked
+    }
+}
+
+pub fn store_kyc_record(
     storage: &mut dyn Storage,
     address: &CanonicalAddr,
-    is_accepted: bool,
+    record: &KycRecord,
 ) -> StdResult<()> {
-    Ok(Bucket::<bool>::new(storage, PREFIX_KEY_TOU_ADDRESS)
-        .save(address, &is_accepted)?)
+    Bucket::<KycRecord>::new(storage, PREFIX_KEY_KYC_RECORD).save(address, record)
 }
 
-pub fn read_tou_address(
+// registrations made before records existed are dropped together with the record
+pub fn remove_kyc_record(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+) {
+    Bucket::<KycRecord>::new(storage, PREFIX_KEY_KYC_RECORD).remove(address);
+    Bucket::<bool>::new(storage, PREFIX_KEY_KYC_ADDRESS).remove(address);
+}
+
+// falls back to the legacy bool registration, a basic level verification which never expires
+pub fn read_kyc_record(
     storage: &dyn Storage,
     address: &CanonicalAddr,
-) -> StdResult<bool> {
-    match ReadonlyBucket::new(storage, PREFIX_KEY_TOU_ADDRESS).may_load(address)? {
-        Some(found) => Ok(found),
-        None => Ok(false),
+) -> StdResult<Option<KycRecord>> {
+    if let Some(record) = ReadonlyBucket::new(storage, PREFIX_KEY_KYC_RECORD).may_load(address)? {
+        return Ok(Some(record));
+    }
+
+    match ReadonlyBucket::<bool>::new(storage, PREFIX_KEY_KYC_ADDRESS).may_load(address)? {
+        Some(true) => Ok(Some(KycRecord {
+            provider: None,
+            verified_at: 0,
+            expires_at: None,
+            level: KycLevel::Basic,
+            country: None,
+            reference_hashes: vec![],
+        })),
+        _ => Ok(None),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TermsOfUse {
+    pub content_hash: String,
+    pub effective_from: u64,
+}
+
+pub fn store_terms_of_use(
+    storage: &mut dyn Storage,
+    version: u32,
+    terms_of_use: &TermsOfUse,
+) -> StdResult<()> {
+    Bucket::<TermsOfUse>::new(storage, PREFIX_KEY_TOU_VERSION).save(&version.to_be_bytes(), terms_of_use)
+}
+
+pub fn read_terms_of_use(
+    storage: &dyn Storage,
+    version: u32,
+) -> StdResult<Option<TermsOfUse>> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_TOU_VERSION).may_load(&version.to_be_bytes())
+}
+
+pub fn read_latest_terms_of_use(storage: &dyn Storage) -> StdResult<Option<(u32, TermsOfUse)>> {
+    find_latest_terms_of_use(storage, |_| true)
+}
+
+// the latest version whose effective date has passed, 0 until the first one takes effect
+pub fn read_current_tou_version(storage: &dyn Storage, block_time: u64) -> StdResult<u32> {
+    Ok(find_latest_terms_of_use(storage, |terms_of_use| terms_of_use.effective_from <= block_time)?
+        .map_or(0, |(version, _)| version))
+}
+
+fn find_latest_terms_of_use(
+    storage: &dyn Storage,
+    predicate: impl Fn(&TermsOfUse) -> bool,
+) -> StdResult<Option<(u32, TermsOfUse)>> {
+    let versions: ReadonlyBucket<TermsOfUse> = ReadonlyBucket::new(storage, PREFIX_KEY_TOU_VERSION);
+    for item in versions.range(None, None, Order::Descending) {
+        let (k, terms_of_use) = item?;
+        if predicate(&terms_of_use) {
+            let mut version = [0u8; 4];
+            version.copy_from_slice(&k);
+            return Ok(Some((u32::from_be_bytes(version), terms_of_use)));
+        }
+    }
+
+    Ok(None)
+}
+
+pub fn store_accepted_tou_version(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+    version: u32,
+) -> StdResult<()> {
+    Bucket::<u32>::new(storage, PREFIX_KEY_TOU_ACCEPTANCE).save(address, &version)
+}
+
+pub fn remove_accepted_tou_version(storage: &mut dyn Storage, address: &CanonicalAddr) {
+    Bucket::<u32>::new(storage, PREFIX_KEY_TOU_ACCEPTANCE).remove(address);
+    Bucket::<bool>::new(storage, PREFIX_KEY_TOU_ADDRESS).remove(address);
+}
+
+// falls back to the legacy bool acceptance, which stands for version 0
+pub fn read_accepted_tou_version(
+    storage: &dyn Storage,
+    address: &CanonicalAddr,
+) -> StdResult<Option<u32>> {
+    if let Some(version) = ReadonlyBucket::new(storage, PREFIX_KEY_TOU_ACCEPTANCE).may_load(address)? {
+        return Ok(Some(version));
+    }
+
+    match ReadonlyBucket::<bool>::new(storage, PREFIX_KEY_TOU_ADDRESS).may_load(address)? {
+        Some(true) => Ok(Some(0)),
+        _ => Ok(None),
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BlockedAddress {
+    pub blocked_at: u64,
+    pub blocked_by: CanonicalAddr,
+}
+
+pub fn store_blocked_address(
+    storage: &mut dyn Storage,
+    address: &CanonicalAddr,
+    blocked_address: &BlockedAddress,
+) -> StdResult<()> {
+    Bucket::<BlockedAddress>::new(storage, PREFIX_KEY_BLOCKED_ADDRESS).save(address, blocked_address)
+}
+
+pub fn remove_blocked_address(storage: &mut dyn Storage, address: &CanonicalAddr) {
+    Bucket::<BlockedAddress>::new(storage, PREFIX_KEY_BLOCKED_ADDRESS).remove(address);
+}
+
+pub fn read_blocked_address(storage: &dyn Storage, address: &CanonicalAddr) -> StdResult<Option<BlockedAddress>> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_BLOCKED_ADDRESS).may_load(address)
+}
+
+pub fn is_address_blocked(storage: &dyn Storage, address: &CanonicalAddr) -> StdResult<bool> {
+    Ok(read_blocked_address(storage, address)?.is_some())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AddressLink {
+    pub old_address: CanonicalAddr,
+    pub new_address: CanonicalAddr,
+    pub linked_at: u64,
+    pub linked_by: CanonicalAddr,
+}
+
+// the link is kept under both addresses, so it can be looked up from either side
+pub fn store_address_link(storage: &mut dyn Storage, link: &AddressLink) -> StdResult<()> {
+    Bucket::<AddressLink>::new(storage, PREFIX_KEY_LINK_TO).save(&link.old_address, link)?;
+    Bucket::<AddressLink>::new(storage, PREFIX_KEY_LINK_FROM).save(&link.new_address, link)
+}
+
+pub fn read_address_link_to(storage: &dyn Storage, address: &CanonicalAddr) -> StdResult<Option<AddressLink>> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_LINK_TO).may_load(address)
+}
+
+pub fn read_address_link_from(storage: &dyn Storage, address: &CanonicalAddr) -> StdResult<Option<AddressLink>> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_LINK_FROM).may_load(address)
+}
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+pub fn read_kyc_records(
+    storage: &dyn Storage,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<Vec<(CanonicalAddr, KycRecord)>> {
+    read_range(storage, PREFIX_KEY_KYC_RECORD, start_after, limit, order_by)
+}
+
+pub fn read_accepted_tou_versions(
+    storage: &dyn Storage,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<Vec<(CanonicalAddr, u32)>> {
+    read_range(storage, PREFIX_KEY_TOU_ACCEPTANCE, start_after, limit, order_by)
+}
+
+pub fn read_blocked_addresses(
+    storage: &dyn Storage,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<Vec<(CanonicalAddr, BlockedAddress)>> {
+    read_range(storage, PREFIX_KEY_BLOCKED_ADDRESS, start_after, limit, order_by)
+}
+
+fn read_range<T: Serialize + DeserializeOwned>(
+    storage: &dyn Storage,
+    namespace: &[u8],
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<Vec<(CanonicalAddr, T)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let (start, end, order_by) = match order_by {
+        Some(OrderBy::Asc) => (calc_range_start_addr(start_after), None, OrderBy::Asc),
+        _ => (None, calc_range_end_addr(start_after), OrderBy::Desc),
+    };
+
+    let bucket: ReadonlyBucket<T> = ReadonlyBucket::new(storage, namespace);
+    bucket
+        .range(start.as_deref(), end.as_deref(), order_by.into())
+        .take(limit)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+// this will set the first key after the provided key, by appending a 1 byte
+fn calc_range_start_addr(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
+    start_after.map(|addr| {
+        let mut v = addr.as_slice().to_vec();
+        v.push(1);
+        v
+    })
+}
+
+// range ends are exclusive, so the provided key itself is skipped
+fn calc_range_end_addr(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
+    start_after.map(|addr| addr.as_slice().to_vec())
+}
+
+// bool registrations and acceptances stored before records existed
+pub fn read_legacy_kyc_addresses(storage: &dyn Storage, limit: usize) -> StdResult<Vec<(CanonicalAddr, bool)>> {
+    read_legacy_entries(storage, PREFIX_KEY_KYC_ADDRESS, limit)
+}
+
+pub fn read_legacy_tou_addresses(storage: &dyn Storage, limit: usize) -> StdResult<Vec<(CanonicalAddr, bool)>> {
+    read_legacy_entries(storage, PREFIX_KEY_TOU_ADDRESS, limit)
+}
+
+pub fn remove_legacy_kyc_address(storage: &mut dyn Storage, address: &CanonicalAddr) {
+    Bucket::<bool>::new(storage, PREFIX_KEY_KYC_ADDRESS).remove(address);
+}
+
+pub fn remove_legacy_tou_address(storage: &mut dyn Storage, address: &CanonicalAddr) {
+    Bucket::<bool>::new(storage, PREFIX_KEY_TOU_ADDRESS).remove(address);
+}
+
+fn read_legacy_entries(
+    storage: &dyn Storage,
+    namespace: &[u8],
+    limit: usize,
+) -> StdResult<Vec<(CanonicalAddr, bool)>> {
+    let bucket: ReadonlyBucket<bool> = ReadonlyBucket::new(storage, namespace);
+    bucket
+        .range(None, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+// stats only follow stored records, legacy entries are counted once they are migrated
+pub fn is_kyc_record_stored(storage: &dyn Storage, address: &CanonicalAddr) -> StdResult<bool> {
+    Ok(ReadonlyBucket::<KycRecord>::new(storage, PREFIX_KEY_KYC_RECORD).may_load(address)?.is_some())
+}
+
+pub fn is_tou_acceptance_stored(storage: &dyn Storage, address: &CanonicalAddr) -> StdResult<bool> {
+    Ok(ReadonlyBucket::<u32>::new(storage, PREFIX_KEY_TOU_ACCEPTANCE).may_load(address)?.is_some())
+}
+
 pub fn store_pending_owner(storage: &mut dyn Storage, new_owner: &CanonicalAddr) -> StdResult<()> {
     singleton(storage, KEY_PENDING_OWNER).save(new_owner)
 }
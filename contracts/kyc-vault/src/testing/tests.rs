@@ -1,7 +1,10 @@
-use crate::contract::{ query, instantiate, execute};
-use starterra_token::kyc_vault::{ConfigResponse, QueryMsg, IsVerifiedResponse, IsAcceptedResponse, IsAcceptedVerifiedResponse, InstantiateMsg, ExecuteMsg};
+use crate::contract::{ query, instantiate, execute, migrate};
+use starterra_token::kyc_vault::{ConfigResponse, QueryMsg, IsVerifiedResponse, IsAcceptedResponse, IsAcceptedVerifiedResponse, InstantiateMsg, ExecuteMsg, KycLevel, KycRecordResponse, IsVerifiedAtLevelResponse, ProvidersResponse, ProviderResponse, TermsOfUseResponse, VerifiedAddressesResponse, AcceptedAddressesResponse, AcceptedAddressResponse, StatsResponse, MigrateMsg, BlockedAddressesResponse, BlockedAddressResponse, AddressLinksResponse, AddressLinkResponse};
+use starterra_token::common::OrderBy;
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{from_binary, attr};
+use cosmwasm_std::{from_binary, attr, Api, Binary, Storage};
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+use cosmwasm_storage::{to_length_prefixed, Bucket};
 use crate::errors::ContractError;
 
 #[test]
@@ -10,7 +13,8 @@ fn proper_initialization() {
     let info = mock_info("newaddr", &vec![]);
     let msg = InstantiateMsg {
         owner: String::from("newaddr"),
-        kyc_provider_address: String::from("kyc_provider"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
     };
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -19,7 +23,7 @@ fn proper_initialization() {
         from_binary::<ConfigResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap(),
         ConfigResponse {
             owner: String::from("newaddr"),
-            kyc_provider_address: String::from("kyc_provider"),
+            default_validity: 0,
         }
     );
 }
@@ -30,7 +34,8 @@ fn update_config() {
 
     let msg = InstantiateMsg {
         owner: String::from("owner"),
-        kyc_provider_address: String::from("kyc_provider"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
     };
 
     let info = mock_info("addr0000", &vec![]);
@@ -38,7 +43,7 @@ fn update_config() {
 
     let msg = ExecuteMsg::UpdateConfig {
         owner: Some(String::from("owner2")),
-        kyc_provider_address: Some(String::from("kyc_provider2")),
+        default_validity: Some(100),
     };
     let info = mock_info("owner", &vec![]);
     let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -51,13 +56,13 @@ fn update_config() {
         from_binary::<ConfigResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap(),
         ConfigResponse {
             owner: String::from("owner2"),
-            kyc_provider_address: String::from("kyc_provider2"),
+            default_validity: 100,
         }
     );
 
     let msg = ExecuteMsg::UpdateConfig {
         owner: Some(String::from("owner3")),
-        kyc_provider_address: Some(String::from("kyc_provider3")),
+        default_validity: None,
     };
     let info = mock_info("owner", &vec![]);
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
@@ -68,7 +73,7 @@ fn update_config() {
 
     let msg = ExecuteMsg::UpdateConfig {
         owner: None,
-        kyc_provider_address: Some(String::from("kyc_provider3")),
+        default_validity: Some(0),
     };
     let info = mock_info("owner2", &vec![]);
     let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -77,7 +82,7 @@ fn update_config() {
         from_binary::<ConfigResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap(),
         ConfigResponse {
             owner: String::from("owner2"),
-            kyc_provider_address: String::from("kyc_provider3"),
+            default_validity: 0,
         }
     );
 }
@@ -89,7 +94,8 @@ fn accept_ownership() {
 
     let msg = InstantiateMsg {
         owner: String::from("owner"),
-        kyc_provider_address: String::from("kyc_provider"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
     };
     let info = mock_info("addr0000", &vec![]);
     let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -105,7 +111,7 @@ fn accept_ownership() {
 
     let msg = ExecuteMsg::UpdateConfig {
         owner: Some(String::from("new_owner")),
-        kyc_provider_address: None
+        default_validity: None,
     };
     let info = mock_info("owner", &vec![]);
     let env = mock_env();
@@ -139,7 +145,8 @@ fn register_kyc_address() {
 
     let msg = InstantiateMsg {
         owner: String::from("owner"),
-        kyc_provider_address: String::from("kyc_provider"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
     };
 
     let info = mock_info("addr0000", &vec![]);
@@ -147,6 +154,10 @@ fn register_kyc_address() {
 
     let msg = ExecuteMsg::RegisterAddress {
         address: String::from("KYC_ADDRESS1"),
+        expires_at: None,
+        level: None,
+        country: None,
+        reference_hashes: None,
     };
     let info = mock_info("wrong_kyc_provider", &[]);
     let res = execute(deps.as_mut(), mock_env(), info.clone(),msg.clone());
@@ -163,6 +174,7 @@ fn register_kyc_address() {
         vec![
             attr("action", "register_kyc_address"),
             attr("registered_kyc_address", "KYC_ADDRESS1"),
+            attr("provider", "kyc_provider"),
         ]
     );
 
@@ -180,6 +192,7 @@ fn register_kyc_address() {
         IsVerifiedResponse {
             address: String::from("KYC_ADDRESS1"),
             is_verified: true,
+            expires_at: None,
         }
     );
 
@@ -197,6 +210,7 @@ fn register_kyc_address() {
         IsVerifiedResponse {
             address: String::from("KYC_ADDRESS2"),
             is_verified: false,
+            expires_at: None,
         }
     );
 }
@@ -207,7 +221,8 @@ fn register_kyc_addresses() {
 
     let msg = InstantiateMsg {
         owner: String::from("owner"),
-        kyc_provider_address: String::from("kyc_provider"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
     };
 
     let info = mock_info("addr0000", &vec![]);
@@ -215,6 +230,9 @@ fn register_kyc_addresses() {
 
     let msg = ExecuteMsg::RegisterAddresses {
         addresses: vec![String::from("KYC_ADDRESS1"), String::from("KYC_ADDRESS2"), ],
+        expires_at: None,
+        level: None,
+        country: None,
     };
     let info = mock_info("wrong_kyc_provider", &[]);
     let res = execute(deps.as_mut(), mock_env(), info.clone(),msg.clone());
@@ -231,6 +249,7 @@ fn register_kyc_addresses() {
         vec![
             attr("action", "register_kyc_addresses"),
             attr("registered_kyc_addresses", "KYC_ADDRESS1,KYC_ADDRESS2"),
+            attr("provider", "kyc_provider"),
         ]
     );
 
@@ -247,6 +266,7 @@ fn register_kyc_addresses() {
         IsVerifiedResponse {
             address: String::from("KYC_ADDRESS1"),
             is_verified: true,
+            expires_at: None,
         }
     );
     assert_eq!(
@@ -262,6 +282,7 @@ fn register_kyc_addresses() {
         IsVerifiedResponse {
             address: String::from("KYC_ADDRESS2"),
             is_verified: true,
+            expires_at: None,
         }
     );
 
@@ -278,6 +299,7 @@ fn register_kyc_addresses() {
         IsVerifiedResponse {
             address: String::from("KYC_ADDRESS3"),
             is_verified: false,
+            expires_at: None,
         }
     );
 }
@@ -288,7 +310,8 @@ fn unregister_kyc_address() {
 
     let msg = InstantiateMsg {
         owner: String::from("owner"),
-        kyc_provider_address: String::from("kyc_provider"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
     };
 
     let info = mock_info("addr0000", &vec![]);
@@ -296,6 +319,10 @@ fn unregister_kyc_address() {
 
     let msg = ExecuteMsg::RegisterAddress {
         address: String::from("KYC_ADDRESS1"),
+        expires_at: None,
+        level: None,
+        country: None,
+        reference_hashes: None,
     };
     let info = mock_info("wrong_kyc_provider", &[]);
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone());
@@ -312,6 +339,7 @@ fn unregister_kyc_address() {
         vec![
             attr("action", "register_kyc_address"),
             attr("registered_kyc_address", "KYC_ADDRESS1"),
+            attr("provider", "kyc_provider"),
         ]
     );
 
@@ -328,6 +356,7 @@ fn unregister_kyc_address() {
         IsVerifiedResponse {
             address: String::from("KYC_ADDRESS1"),
             is_verified: true,
+            expires_at: None,
         }
     );
 
@@ -359,6 +388,7 @@ fn unregister_kyc_address() {
         IsVerifiedResponse {
             address: String::from("KYC_ADDRESS1"),
             is_verified: false,
+            expires_at: None,
         }
     );
 }
@@ -369,7 +399,8 @@ fn unregister_kyc_addresses() {
 
     let msg = InstantiateMsg {
         owner: String::from("owner"),
-        kyc_provider_address: String::from("kyc_provider"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
     };
 
     let info = mock_info("addr0000", &vec![]);
@@ -377,6 +408,9 @@ fn unregister_kyc_addresses() {
 
     let msg = ExecuteMsg::RegisterAddresses {
         addresses: vec![String::from("KYC_ADDRESS1"), String::from("KYC_ADDRESS2"), ],
+        expires_at: None,
+        level: None,
+        country: None,
     };
 
     let info = mock_info("wrong_kyc_provider", &[]);
@@ -401,6 +435,7 @@ fn unregister_kyc_addresses() {
         IsVerifiedResponse {
             address: String::from("KYC_ADDRESS2"),
             is_verified: true,
+            expires_at: None,
         }
     );
 
@@ -433,6 +468,7 @@ fn unregister_kyc_addresses() {
         IsVerifiedResponse {
             address: String::from("KYC_ADDRESS1"),
             is_verified: false,
+            expires_at: None,
         }
     );
 
@@ -449,6 +485,7 @@ fn unregister_kyc_addresses() {
         IsVerifiedResponse {
             address: String::from("KYC_ADDRESS2"),
             is_verified: false,
+            expires_at: None,
         }
     );
 }
@@ -459,13 +496,16 @@ fn accept_terms_of_use() {
 
     let msg = InstantiateMsg {
         owner: String::from("owner"),
-        kyc_provider_address: String::from("kyc_provider"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
     };
 
     let info = mock_info("addr0000", &vec![]);
     let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
-    let msg = ExecuteMsg::AcceptTermsOfUse {};
+    let msg = ExecuteMsg::AcceptTermsOfUse {
+        version: 0,
+    };
     let info = mock_info("user1", &[]);
     let res = execute(deps.as_mut(), mock_env(), info.clone(),msg.clone()).unwrap();
 
@@ -474,6 +514,7 @@ fn accept_terms_of_use() {
         vec![
             attr("action", "accept_terms_of_use"),
             attr("address", "user1"),
+            attr("version", "0"),
         ]
     );
 
@@ -490,6 +531,8 @@ fn accept_terms_of_use() {
         IsAcceptedResponse {
             address: String::from("user1"),
             is_accepted: true,
+            accepted_version: Some(0),
+            current_version: 0,
         }
     );
 
@@ -506,6 +549,8 @@ fn accept_terms_of_use() {
         IsAcceptedResponse {
             address: String::from("user2"),
             is_accepted: false,
+            accepted_version: None,
+            current_version: 0,
         }
     );
 }
@@ -516,7 +561,8 @@ fn is_accepted_and_verified_query() {
 
     let msg = InstantiateMsg {
         owner: String::from("owner"),
-        kyc_provider_address: String::from("kyc_provider"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
     };
 
     let info = mock_info("addr0000", &vec![]);
@@ -536,10 +582,14 @@ fn is_accepted_and_verified_query() {
             address: String::from("user1"),
             is_accepted: false,
             is_verified: false,
+            expires_at: None,
+            is_blocked: false,
         }
     );
 
-    let msg = ExecuteMsg::AcceptTermsOfUse {};
+    let msg = ExecuteMsg::AcceptTermsOfUse {
+        version: 0,
+    };
     let info = mock_info("user1", &[]);
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
 
@@ -548,6 +598,7 @@ fn is_accepted_and_verified_query() {
         vec![
             attr("action", "accept_terms_of_use"),
             attr("address", "user1"),
+            attr("version", "0"),
         ]
     );
 
@@ -565,6 +616,8 @@ fn is_accepted_and_verified_query() {
             address: String::from("user1"),
             is_accepted: true,
             is_verified: false,
+            expires_at: None,
+            is_blocked: false,
         }
     );
 
@@ -581,12 +634,17 @@ fn is_accepted_and_verified_query() {
         IsAcceptedResponse {
             address: String::from("user2"),
             is_accepted: false,
+            accepted_version: None,
+            current_version: 0,
         }
     );
 
     //register kyc
     let msg = ExecuteMsg::RegisterAddresses {
         addresses: vec![String::from("user1")],
+        expires_at: None,
+        level: None,
+        country: None,
     };
     let info = mock_info("kyc_provider", &[]);
     let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -605,6 +663,979 @@ fn is_accepted_and_verified_query() {
             address: String::from("user1"),
             is_accepted: true,
             is_verified: true,
+            expires_at: None,
+            is_blocked: false,
+        }
+    );
+}
+
+#[test]
+fn expiring_kyc_verification() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 100,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let now = mock_env().block.time.seconds();
+    let info = mock_info("kyc_provider", &[]);
+    let msg = ExecuteMsg::RegisterAddress {
+        address: String::from("KYC_ADDRESS1"),
+        expires_at: Some(now),
+        level: None,
+        country: None,
+        reference_hashes: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::ExpiryInThePast {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // default validity applies without an explicit expiry
+    let msg = ExecuteMsg::RegisterAddresses {
+        addresses: vec![String::from("KYC_ADDRESS1")],
+        expires_at: None,
+        level: None,
+        country: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::RegisterAddress {
+        address: String::from("KYC_ADDRESS2"),
+        expires_at: Some(now + 1000),
+        level: None,
+        country: None,
+        reference_hashes: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    assert_eq!(
+        from_binary::<IsAcceptedVerifiedResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::IsAcceptedVerified {
+            address: String::from("KYC_ADDRESS1"),
+        }).unwrap()).unwrap(),
+        IsAcceptedVerifiedResponse {
+            address: String::from("KYC_ADDRESS1"),
+            is_accepted: false,
+            is_verified: true,
+            expires_at: Some(now + 100),
+            is_blocked: false,
+        }
+    );
+
+    // expired records are reported as unverified with their expiry
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    assert_eq!(
+        from_binary::<IsVerifiedResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::IsVerified {
+            address: String::from("KYC_ADDRESS1"),
+        }).unwrap()).unwrap(),
+        IsVerifiedResponse {
+            address: String::from("KYC_ADDRESS1"),
+            is_verified: false,
+            expires_at: Some(now + 100),
+        }
+    );
+    assert_eq!(
+        from_binary::<IsVerifiedResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::IsVerified {
+            address: String::from("KYC_ADDRESS2"),
+        }).unwrap()).unwrap(),
+        IsVerifiedResponse {
+            address: String::from("KYC_ADDRESS2"),
+            is_verified: true,
+            expires_at: Some(now + 1000),
+        }
+    );
+
+    // registrations stored as a bare bool stay valid until unregistered
+    let legacy_address = deps.api.addr_canonicalize("KYC_ADDRESS3").unwrap();
+    Bucket::<bool>::new(&mut deps.storage, b"kyc_address").save(legacy_address.as_slice(), &true).unwrap();
+    assert_eq!(
+        from_binary::<IsVerifiedResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::IsVerified {
+            address: String::from("KYC_ADDRESS3"),
+        }).unwrap()).unwrap(),
+        IsVerifiedResponse {
+            address: String::from("KYC_ADDRESS3"),
+            is_verified: true,
+            expires_at: None,
+        }
+    );
+
+    let msg = ExecuteMsg::UnregisterAddress {
+        address: String::from("KYC_ADDRESS3"),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        from_binary::<IsVerifiedResponse>(&query(deps.as_ref(), env, QueryMsg::IsVerified {
+            address: String::from("KYC_ADDRESS3"),
+        }).unwrap()).unwrap(),
+        IsVerifiedResponse {
+            address: String::from("KYC_ADDRESS3"),
+            is_verified: false,
+            expires_at: None,
+        }
+    );
+}
+
+#[test]
+fn kyc_levels_and_countries() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let reference_hash = String::from("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08");
+    let info = mock_info("kyc_provider", &[]);
+    let msg = ExecuteMsg::RegisterAddress {
+        address: String::from("KYC_ADDRESS1"),
+        expires_at: None,
+        level: Some(KycLevel::Enhanced),
+        country: Some(String::from("USA")),
+        reference_hashes: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidCountryCode {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::RegisterAddress {
+        address: String::from("KYC_ADDRESS1"),
+        expires_at: None,
+        level: Some(KycLevel::Enhanced),
+        country: Some(String::from("de")),
+        reference_hashes: Some(vec![String::from("john doe")]),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidReferenceHash {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::RegisterAddress {
+        address: String::from("KYC_ADDRESS1"),
+        expires_at: None,
+        level: Some(KycLevel::Enhanced),
+        country: Some(String::from("de")),
+        reference_hashes: Some(vec![reference_hash.clone()]),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::RegisterAddresses {
+        addresses: vec![String::from("KYC_ADDRESS2")],
+        expires_at: None,
+        level: None,
+        country: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    assert_eq!(
+        from_binary::<KycRecordResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::KycRecord {
+            address: String::from("KYC_ADDRESS1"),
+        }).unwrap()).unwrap(),
+        KycRecordResponse {
+            address: String::from("KYC_ADDRESS1"),
+            is_verified: true,
+            provider: Some(String::from("kyc_provider")),
+            level: Some(KycLevel::Enhanced),
+            country: Some(String::from("DE")),
+            reference_hashes: vec![reference_hash],
+            verified_at: Some(mock_env().block.time.seconds()),
+            expires_at: None,
+        }
+    );
+
+    let is_verified_at_level = |address: &str, min_level: KycLevel, blocked_countries: Vec<&str>| {
+        from_binary::<IsVerifiedAtLevelResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::IsVerifiedAtLevel {
+            address: String::from(address),
+            min_level,
+            blocked_countries: blocked_countries.into_iter().map(String::from).collect(),
+        }).unwrap()).unwrap().is_verified
+    };
+    assert!(is_verified_at_level("KYC_ADDRESS1", KycLevel::Enhanced, vec!["US"]));
+    assert!(!is_verified_at_level("KYC_ADDRESS1", KycLevel::Accredited, vec![]));
+    assert!(!is_verified_at_level("KYC_ADDRESS1", KycLevel::Basic, vec!["us", "de"]));
+    assert!(is_verified_at_level("KYC_ADDRESS2", KycLevel::Basic, vec![]));
+    assert!(!is_verified_at_level("KYC_ADDRESS2", KycLevel::Basic, vec!["US"]));
+    assert!(!is_verified_at_level("KYC_ADDRESS3", KycLevel::Basic, vec![]));
+}
+
+#[test]
+fn kyc_provider_registry() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::AddProvider {
+        address: String::from("kyc_provider2"),
+        pubkey: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg.clone()).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg);
+    match res {
+        Err(ContractError::ProviderAlreadyRegistered {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let added_at = mock_env().block.time.seconds();
+    assert_eq!(
+        from_binary::<ProvidersResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Providers {}).unwrap()).unwrap(),
+        ProvidersResponse {
+            providers: vec![
+                // ordered by canonical address
+                ProviderResponse {
+                    address: String::from("kyc_provider2"),
+                    added_at,
+                    pubkey: None,
+                },
+                ProviderResponse {
+                    address: String::from("kyc_provider"),
+                    added_at,
+                    pubkey: None,
+                },
+            ],
+        }
+    );
+
+    for (provider, address) in [("kyc_provider", "KYC_ADDRESS1"), ("kyc_provider2", "KYC_ADDRESS2")] {
+        let msg = ExecuteMsg::RegisterAddress {
+            address: String::from(address),
+            expires_at: None,
+            level: None,
+            country: None,
+            reference_hashes: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info(provider, &[]), msg).unwrap();
+    }
+    let kyc_record = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, address: &str| {
+        from_binary::<KycRecordResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::KycRecord {
+            address: String::from(address),
+        }).unwrap()).unwrap()
+    };
+    assert_eq!(kyc_record(&deps, "KYC_ADDRESS2").provider, Some(String::from("kyc_provider2")));
+    assert!(kyc_record(&deps, "KYC_ADDRESS2").is_verified);
+
+    // removing without invalidation keeps the provider's verifications
+    let msg = ExecuteMsg::RemoveProvider {
+        address: String::from("kyc_provider"),
+        invalidate_verifications: false,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg.clone()).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg);
+    match res {
+        Err(ContractError::ProviderNotRegistered {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    assert!(kyc_record(&deps, "KYC_ADDRESS1").is_verified);
+
+    let msg = ExecuteMsg::RemoveProvider {
+        address: String::from("kyc_provider2"),
+        invalidate_verifications: true,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    assert!(!kyc_record(&deps, "KYC_ADDRESS2").is_verified);
+    assert_eq!(
+        from_binary::<ProvidersResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Providers {}).unwrap()).unwrap(),
+        ProvidersResponse {
+            providers: vec![],
+        }
+    );
+
+    let msg = ExecuteMsg::RegisterAddress {
+        address: String::from("KYC_ADDRESS3"),
+        expires_at: None,
+        level: None,
+        country: None,
+        reference_hashes: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider2", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // verifications written after re-adding the provider are valid again
+    let msg_add = ExecuteMsg::AddProvider {
+        address: String::from("kyc_provider2"),
+        pubkey: None,
+    };
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(10);
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg_add).unwrap();
+    let _res = execute(deps.as_mut(), env, mock_info("kyc_provider2", &[]), msg).unwrap();
+    assert!(kyc_record(&deps, "KYC_ADDRESS3").is_verified);
+    assert!(!kyc_record(&deps, "KYC_ADDRESS2").is_verified);
+}
+
+#[test]
+fn versioned_terms_of_use() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let now = mock_env().block.time.seconds();
+    let content_hash = String::from("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08");
+    let msg = ExecuteMsg::PublishTermsOfUse {
+        content_hash: content_hash.clone(),
+        effective_from: now + 100,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), ExecuteMsg::PublishTermsOfUse {
+        content_hash: String::from("terms of use"),
+        effective_from: now + 100,
+    });
+    match res {
+        Err(ContractError::InvalidContentHash {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), ExecuteMsg::PublishTermsOfUse {
+        content_hash: content_hash.clone(),
+        effective_from: now - 1,
+    });
+    match res {
+        Err(ContractError::InvalidEffectiveDate {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "publish_terms_of_use"),
+            attr("version", "1"),
+            attr("effective_from", (now + 100).to_string()),
+        ]
+    );
+
+    // the published version can be accepted before it takes effect
+    let msg = ExecuteMsg::AcceptTermsOfUse {
+        version: 0,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg.clone()).unwrap();
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("user2", &[]), msg.clone()).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), mock_info("user2", &[]), msg.clone());
+    match res {
+        Err(ContractError::TouAlreadyAccepted {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let msg_v1 = ExecuteMsg::AcceptTermsOfUse {
+        version: 1,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg_v1.clone()).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), ExecuteMsg::AcceptTermsOfUse {
+        version: 2,
+    });
+    match res {
+        Err(ContractError::InvalidTermsOfUseVersion {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    assert_eq!(
+        from_binary::<TermsOfUseResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::TermsOfUse {
+            version: None,
+        }).unwrap()).unwrap(),
+        TermsOfUseResponse {
+            version: 1,
+            content_hash,
+            effective_from: now + 100,
+        }
+    );
+    let is_accepted = |address: &str| {
+        from_binary::<IsAcceptedResponse>(&query(deps.as_ref(), env.clone(), QueryMsg::IsAccepted {
+            address: String::from(address),
+        }).unwrap()).unwrap()
+    };
+    assert_eq!(
+        is_accepted("user1"),
+        IsAcceptedResponse {
+            address: String::from("user1"),
+            is_accepted: true,
+            accepted_version: Some(1),
+            current_version: 1,
+        }
+    );
+    assert_eq!(
+        is_accepted("user2"),
+        IsAcceptedResponse {
+            address: String::from("user2"),
+            is_accepted: false,
+            accepted_version: Some(0),
+            current_version: 1,
+        }
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("user3", &[]), msg);
+    match res {
+        Err(ContractError::InvalidTermsOfUseVersion {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = execute(deps.as_mut(), env, mock_info("user2", &[]), msg_v1).unwrap();
+}
+
+#[test]
+fn enumerate_addresses_and_stats() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterAddresses {
+        addresses: vec![String::from("user1"), String::from("user2"), String::from("user3")],
+        expires_at: None,
+        level: None,
+        country: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg.clone()).unwrap();
+    // registering again only refreshes the records
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg).unwrap();
+    for user in ["user3", "user4"] {
+        let msg = ExecuteMsg::AcceptTermsOfUse {
+            version: 0,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info(user, &[]), msg).unwrap();
+    }
+    let msg = ExecuteMsg::UnregisterAddress {
+        address: String::from("user2"),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg).unwrap();
+
+    assert_eq!(
+        from_binary::<StatsResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap(),
+        StatsResponse {
+            total_verified: 2,
+            total_accepted: 2,
+            total_verified_accepted: 1,
+        }
+    );
+
+    let verified_addresses = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, start_after: Option<&str>, order_by: OrderBy| -> Vec<String> {
+        from_binary::<VerifiedAddressesResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::VerifiedAddresses {
+            start_after: start_after.map(String::from),
+            limit: Some(1),
+            order_by: Some(order_by),
+        }).unwrap()).unwrap().records.into_iter().map(|record| record.address).collect()
+    };
+    assert_eq!(verified_addresses(&deps, None, OrderBy::Asc), vec![String::from("user1")]);
+    assert_eq!(verified_addresses(&deps, Some("user1"), OrderBy::Asc), vec![String::from("user3")]);
+    assert_eq!(verified_addresses(&deps, Some("user3"), OrderBy::Asc), Vec::<String>::new());
+    assert_eq!(verified_addresses(&deps, Some("user3"), OrderBy::Desc), vec![String::from("user1")]);
+
+    assert_eq!(
+        from_binary::<AcceptedAddressesResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::AcceptedAddresses {
+            start_after: None,
+            limit: None,
+            order_by: Some(OrderBy::Asc),
+        }).unwrap()).unwrap(),
+        AcceptedAddressesResponse {
+            addresses: vec![
+                // ordered by canonical address
+                AcceptedAddressResponse {
+                    address: String::from("user4"),
+                    accepted_version: 0,
+                },
+                AcceptedAddressResponse {
+                    address: String::from("user3"),
+                    accepted_version: 0,
+                },
+            ],
+        }
+    );
+
+    // legacy bool entries become enumerable and counted after migration
+    let legacy_address = deps.api.addr_canonicalize("user5").unwrap();
+    Bucket::<bool>::new(&mut deps.storage, b"kyc_address").save(legacy_address.as_slice(), &true).unwrap();
+    Bucket::<bool>::new(&mut deps.storage, b"tou_address").save(legacy_address.as_slice(), &true).unwrap();
+    let msg = ExecuteMsg::MigrateLegacyEntries { limit: Some(1) };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the legacy registration is still readable before it is migrated
+    let response = from_binary::<IsAcceptedVerifiedResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::IsAcceptedVerified {
+        address: String::from("user5"),
+    }).unwrap()).unwrap();
+    assert!(response.is_accepted && response.is_verified);
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg.clone()).unwrap();
+    assert_eq!(res.attributes, vec![
+        attr("action", "migrate_legacy_entries"),
+        attr("migrated_entries", "1"),
+    ]);
+    assert_eq!(
+        from_binary::<StatsResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap(),
+        StatsResponse {
+            total_verified: 3,
+            total_accepted: 2,
+            total_verified_accepted: 1,
+        }
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg.clone()).unwrap();
+    assert_eq!(res.attributes[1], attr("migrated_entries", "1"));
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    assert_eq!(res.attributes[1], attr("migrated_entries", "0"));
+
+    assert_eq!(
+        from_binary::<StatsResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap(),
+        StatsResponse {
+            total_verified: 3,
+            total_accepted: 3,
+            total_verified_accepted: 2,
+        }
+    );
+    let records = from_binary::<VerifiedAddressesResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::VerifiedAddresses {
+        start_after: None,
+        limit: None,
+        order_by: None,
+    }).unwrap()).unwrap().records;
+    assert_eq!(records.len(), 3);
+    assert!(records.iter().any(|record| record.address == "user5" && record.is_verified));
+}
+
+#[test]
+fn migrate_baseline_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    // config exactly as stored by the baseline contract
+    let owner = deps.api.addr_canonicalize("owner").unwrap();
+    let kyc_provider = deps.api.addr_canonicalize("kyc_provider").unwrap();
+    let baseline_config = format!(
+        r#"{{"owner":"{}","kyc_provider_address":"{}"}}"#,
+        Binary::from(owner.as_slice()).to_base64(),
+        Binary::from(kyc_provider.as_slice()).to_base64(),
+    );
+    deps.storage.set(&to_length_prefixed(b"config"), baseline_config.as_bytes());
+
+    let _res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    assert_eq!(
+        from_binary::<ConfigResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap(),
+        ConfigResponse {
+            owner: String::from("owner"),
+            default_validity: 0,
+        }
+    );
+    assert_eq!(
+        from_binary::<ProvidersResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Providers {}).unwrap()).unwrap(),
+        ProvidersResponse {
+            providers: vec![ProviderResponse {
+                address: String::from("kyc_provider"),
+                added_at: mock_env().block.time.seconds(),
+                pubkey: None,
+            }],
+        }
+    );
+
+    // the migrated owner keeps its privileges
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        default_validity: Some(1000),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    // migrating again keeps the new config
+    let _res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        from_binary::<ConfigResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap().default_validity,
+        1000
+    );
+}
+
+#[test]
+fn submit_attestation() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let signing_key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+    let msg = ExecuteMsg::AddProvider {
+        address: String::from("kyc_provider2"),
+        pubkey: Some(Binary::from(signing_key.verifying_key().to_bytes().as_slice())),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    let expiry = mock_env().block.time.seconds() + 1000;
+    let sign = |message: String| -> Binary {
+        let signature: Signature = signing_key.sign(message.as_bytes());
+        Binary::from(signature.as_ref())
+    };
+    let contract_address = mock_env().contract.address;
+
+    // signed for another address
+    let msg = ExecuteMsg::SubmitAttestation {
+        expiry,
+        level: KycLevel::Enhanced,
+        signature: sign(format!("{}:user2:enhanced:{}", contract_address, expiry)),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg);
+    match res {
+        Err(ContractError::InvalidAttestation {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::SubmitAttestation {
+        expiry,
+        level: KycLevel::Enhanced,
+        signature: sign(format!("{}:user1:enhanced:{}", contract_address, expiry)),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "submit_attestation"),
+            attr("registered_kyc_address", "user1"),
+            attr("provider", "kyc_provider2"),
+        ]
+    );
+
+    let record = from_binary::<KycRecordResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::KycRecord {
+        address: String::from("user1"),
+    }).unwrap()).unwrap();
+    assert!(record.is_verified);
+    assert_eq!(record.provider, Some(String::from("kyc_provider2")));
+    assert_eq!(record.level, Some(KycLevel::Enhanced));
+    assert_eq!(record.expires_at, Some(expiry));
+
+    // the same attestation can not register the address again after unregistering
+    let msg_unregister = ExecuteMsg::UnregisterAddress {
+        address: String::from("user1"),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg_unregister).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg);
+    match res {
+        Err(ContractError::AttestationAlreadyUsed {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let expired = mock_env().block.time.seconds();
+    let msg = ExecuteMsg::SubmitAttestation {
+        expiry: expired,
+        level: KycLevel::Basic,
+        signature: sign(format!("{}:user1:basic:{}", contract_address, expired)),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg);
+    match res {
+        Err(ContractError::ExpiryInThePast {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn blocklist_overrides_verification() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterAddresses {
+        addresses: vec![String::from("user1"), String::from("user2")],
+        expires_at: None,
+        level: None,
+        country: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg).unwrap();
+    let msg = ExecuteMsg::AcceptTermsOfUse {
+        version: 0,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::BlockAddresses {
+        addresses: vec![String::from("user1"), String::from("user2")],
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "block_addresses"),
+            attr("blocked_addresses", "user1,user2"),
+            attr("sender", "kyc_provider"),
+        ]
+    );
+
+    assert_eq!(
+        from_binary::<IsAcceptedVerifiedResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::IsAcceptedVerified {
+            address: String::from("user1"),
+        }).unwrap()).unwrap(),
+        IsAcceptedVerifiedResponse {
+            address: String::from("user1"),
+            is_accepted: false,
+            is_verified: false,
+            expires_at: None,
+            is_blocked: true,
+        }
+    );
+    let msg = ExecuteMsg::AcceptTermsOfUse {
+        version: 0,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("user2", &[]), msg);
+    match res {
+        Err(ContractError::AddressBlocked {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let blocked_at = mock_env().block.time.seconds();
+    assert_eq!(
+        from_binary::<BlockedAddressesResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::BlockedAddresses {
+            start_after: Some(String::from("user2")),
+            limit: None,
+            order_by: None,
+        }).unwrap()).unwrap(),
+        BlockedAddressesResponse {
+            addresses: vec![BlockedAddressResponse {
+                address: String::from("user1"),
+                blocked_at,
+                blocked_by: String::from("kyc_provider"),
+            }],
+        }
+    );
+
+    // a block added by the owner can only be lifted by the owner
+    let msg = ExecuteMsg::AddProvider {
+        address: String::from("kyc_provider2"),
+        pubkey: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    let msg = ExecuteMsg::BlockAddresses {
+        addresses: vec![String::from("user3")],
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    let msg = ExecuteMsg::BlockAddresses {
+        addresses: vec![String::from("user3")],
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg).unwrap();
+    let msg = ExecuteMsg::UnblockAddresses {
+        addresses: vec![String::from("user3")],
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a provider can not lift another provider's block
+    let msg = ExecuteMsg::UnblockAddresses {
+        addresses: vec![String::from("user2")],
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider2", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::UnblockAddresses {
+        addresses: vec![String::from("user1"), String::from("user3")],
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "unblock_addresses"),
+            attr("unblocked_addresses", "user1,user3"),
+            attr("sender", "owner"),
+        ]
+    );
+    assert_eq!(
+        from_binary::<IsAcceptedVerifiedResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::IsAcceptedVerified {
+            address: String::from("user1"),
+        }).unwrap()).unwrap(),
+        IsAcceptedVerifiedResponse {
+            address: String::from("user1"),
+            is_accepted: true,
+            is_verified: true,
+            expires_at: None,
+            is_blocked: false,
         }
     );
 }
+
+#[test]
+fn link_address() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        kyc_providers: vec![String::from("kyc_provider")],
+        default_validity: 0,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterAddress {
+        address: String::from("user1"),
+        expires_at: None,
+        level: Some(KycLevel::Enhanced),
+        country: None,
+        reference_hashes: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg).unwrap();
+    let msg = ExecuteMsg::AcceptTermsOfUse {
+        version: 0,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::LinkAddress {
+        old: String::from("user1"),
+        new: String::from("user2"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "link_address"),
+            attr("old_address", "user1"),
+            attr("new_address", "user2"),
+            attr("provider", "kyc_provider"),
+        ]
+    );
+    let res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg);
+    match res {
+        Err(ContractError::AddressNotVerified {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let is_accepted_verified = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, address: &str| {
+        let response = from_binary::<IsAcceptedVerifiedResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::IsAcceptedVerified {
+            address: String::from(address),
+        }).unwrap()).unwrap();
+        (response.is_accepted, response.is_verified)
+    };
+    assert_eq!(is_accepted_verified(&deps, "user1"), (false, false));
+    assert_eq!(is_accepted_verified(&deps, "user2"), (true, true));
+    assert_eq!(
+        from_binary::<KycRecordResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::KycRecord {
+            address: String::from("user2"),
+        }).unwrap()).unwrap().level,
+        Some(KycLevel::Enhanced)
+    );
+    assert_eq!(
+        from_binary::<StatsResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap(),
+        StatsResponse {
+            total_verified: 1,
+            total_accepted: 1,
+            total_verified_accepted: 1,
+        }
+    );
+
+    let link = AddressLinkResponse {
+        old_address: String::from("user1"),
+        new_address: String::from("user2"),
+        linked_at: mock_env().block.time.seconds(),
+        linked_by: String::from("kyc_provider"),
+    };
+    assert_eq!(
+        from_binary::<AddressLinksResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::AddressLinks {
+            address: String::from("user1"),
+        }).unwrap()).unwrap(),
+        AddressLinksResponse {
+            address: String::from("user1"),
+            linked_from: None,
+            linked_to: Some(link.clone()),
+        }
+    );
+    assert_eq!(
+        from_binary::<AddressLinksResponse>(&query(deps.as_ref(), mock_env(), QueryMsg::AddressLinks {
+            address: String::from("user2"),
+        }).unwrap()).unwrap(),
+        AddressLinksResponse {
+            address: String::from("user2"),
+            linked_from: Some(link),
+            linked_to: None,
+        }
+    );
+
+    // the new wallet can not take over another verified wallet
+    let msg = ExecuteMsg::RegisterAddress {
+        address: String::from("user3"),
+        expires_at: None,
+        level: None,
+        country: None,
+        reference_hashes: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg).unwrap();
+    let msg = ExecuteMsg::LinkAddress {
+        old: String::from("user3"),
+        new: String::from("user2"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg);
+    match res {
+        Err(ContractError::AddressAlreadyVerified {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a re-verified old wallet can not be linked a second time
+    let msg = ExecuteMsg::RegisterAddress {
+        address: String::from("user1"),
+        expires_at: None,
+        level: None,
+        country: None,
+        reference_hashes: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg).unwrap();
+    let msg = ExecuteMsg::LinkAddress {
+        old: String::from("user1"),
+        new: String::from("user4"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("kyc_provider", &[]), msg);
+    match res {
+        Err(ContractError::AddressAlreadyLinked {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
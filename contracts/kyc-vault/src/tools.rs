@@ -1,14 +1,91 @@
-use cosmwasm_std::{ Response, MessageInfo, Deps};
+use cosmwasm_std::{ Response, MessageInfo, Deps, Env, Storage, CanonicalAddr};
 use crate::errors::ContractError;
-use crate::state::read_config;
+use sha2::{Digest, Sha256};
+use crate::state::{read_config, read_provider, KycRecord};
+use starterra_token::kyc_vault::KycLevel;
+
+pub fn assert_owner_privilege(
+    deps: Deps,
+    info: &MessageInfo,
+) -> Result<Response, ContractError> {
+    if read_config(deps.storage)?.owner != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(Response::default())
+}
+
+pub fn assert_owner_or_kyc_provider_privilege(
+    deps: Deps,
+    info: &MessageInfo,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if read_config(deps.storage)?.owner != sender && read_provider(deps.storage, &sender)?.is_none() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(Response::default())
+}
 
 pub fn assert_kyc_provider_privilege(
     deps: Deps,
     info: MessageInfo
 ) -> Result<Response, ContractError> {
-    if read_config(deps.storage)?.kyc_provider_address != deps.api.addr_canonicalize(info.sender.as_str())? {
+    if read_provider(deps.storage, &deps.api.addr_canonicalize(info.sender.as_str())?)?.is_none() {
         return Err(ContractError::Unauthorized {});
     }
 
     Ok(Response::default())
 }
+
+pub struct KycMetadata {
+    pub level: Option<KycLevel>,
+    pub country: Option<String>,
+    pub reference_hashes: Option<Vec<String>>,
+}
+
+// Without an explicit expiry the verification is valid for the configured default
+pub fn new_kyc_record(
+    storage: &dyn Storage,
+    env: &Env,
+    provider: CanonicalAddr,
+    expires_at: Option<u64>,
+    metadata: KycMetadata,
+) -> Result<KycRecord, ContractError> {
+    let verified_at = env.block.time.seconds();
+    if matches!(expires_at, Some(expires_at) if expires_at <= verified_at) {
+        return Err(ContractError::ExpiryInThePast {});
+    }
+    let country = metadata.country.map(|country| country.to_ascii_uppercase());
+    if matches!(&country, Some(country) if country.len() != 2 || !country.bytes().all(|byte| byte.is_ascii_uppercase())) {
+        return Err(ContractError::InvalidCountryCode {});
+    }
+    let reference_hashes = metadata.reference_hashes.unwrap_or_default();
+    if !reference_hashes.iter().all(|hash| is_sha256_hex(hash)) {
+        return Err(ContractError::InvalidReferenceHash {});
+    }
+    let default_validity = read_config(storage)?.default_validity;
+    let expires_at = match expires_at {
+        Some(expires_at) => Some(expires_at),
+        None if default_validity > 0 => Some(verified_at + default_validity),
+        None => None,
+    };
+
+    Ok(KycRecord {
+        provider: Some(provider),
+        verified_at,
+        expires_at,
+        level: metadata.level.unwrap_or_default(),
+        country,
+        reference_hashes,
+    })
+}
+
+pub fn is_sha256_hex(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+pub fn attestation_message_hash(env: &Env, address: &str, level: KycLevel, expiry: u64) -> Vec<u8> {
+    let message = format!("{}:{}:{}:{}", env.contract.address, address, level.as_str(), expiry);
+    Sha256::digest(message.as_bytes()).to_vec()
+}
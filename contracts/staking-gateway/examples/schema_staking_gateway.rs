@@ -2,7 +2,8 @@ use std::env::current_dir;
 use std::fs::create_dir_all;
 
 use starterra_token::staking_gateway::{
-    ConfigResponse, CanStakeResponse, ExecuteMsg, InstantiateMsg, QueryMsg, AddressesResponse
+    ConfigResponse, CanStakeResponse, ExecuteMsg, InstantiateMsg, QueryMsg, AddressesResponse, StakingContractsResponse,
+    TotalStakedResponse
 };
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
@@ -18,4 +19,6 @@ fn main() {
     export_schema(&schema_for!(ConfigResponse), &out_dir);
     export_schema(&schema_for!(CanStakeResponse), &out_dir);
     export_schema(&schema_for!(AddressesResponse), &out_dir);
+    export_schema(&schema_for!(StakingContractsResponse), &out_dir);
+    export_schema(&schema_for!(TotalStakedResponse), &out_dir);
 }
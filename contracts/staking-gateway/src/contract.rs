@@ -1,15 +1,15 @@
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, to_binary};
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, to_binary};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
 use starterra_token::staking_gateway::{InstantiateMsg, MigrateMsg, QueryMsg, ExecuteMsg};
 
-use crate::state::{Config, store_config};
+use crate::state::{Config, StakingContract, store_config, read_config, store_staking_contract, read_legacy_staking_contracts};
 use crate::errors::ContractError;
-use starterra_token::common::convert_human_to_raw;
-use crate::tools::assert_staking_contracts_len;
-use crate::execute::{update_config, accept_ownership};
-use crate::queries::{query_config, query_can_stake, query_bond_amount, query_addresses};
+use crate::tools::{assert_faction_weights, assert_owner_privilege, convert_faction_weights};
+use crate::execute::{update_config, accept_ownership, add_staking_contract, update_staking_contract, remove_staking_contract, update_bond_checkpoint};
+use crate::queries::{query_config, query_can_stake, query_bond_amount, query_bond_amounts, query_addresses, query_staking_contracts, query_bond_amount_at, query_total_staked_at};
+use crate::querier::load_faction_name;
 
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -19,12 +19,20 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    assert_staking_contracts_len(&msg.staking_contracts)?;
+    for staking_contract in msg.staking_contracts {
+        store_staking_contract(deps.storage, &deps.api.addr_canonicalize(&staking_contract.address)?, &StakingContract {
+            faction: staking_contract.faction,
+            staking_type: staking_contract.staking_type,
+            active: true,
+        })?;
+    }
 
     let config = Config {
         owner: deps.api.addr_canonicalize(&msg.owner)?,
-        staking_contracts: convert_human_to_raw(deps.as_ref(), &msg.staking_contracts)?,
+        multi_faction: msg.multi_faction,
+        faction_weights: convert_faction_weights(deps.as_ref(), &msg.faction_weights)?,
     };
+    assert_faction_weights(deps.as_ref(), &config)?;
 
     store_config(
         deps.storage,
@@ -37,15 +45,43 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { owner, staking_contracts } => {
-            update_config(deps, info, owner, staking_contracts)
-        }
+        ExecuteMsg::UpdateConfig {
+            owner,
+            multi_faction,
+            faction_weights,
+        } => update_config(deps, info, owner, multi_faction, faction_weights),
         ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::AddStakingContract {
+            address,
+            faction,
+            staking_type,
+        } => {
+            assert_owner_privilege(deps.as_ref(), &info)?;
+            add_staking_contract(deps, info, address, faction, staking_type)
+        }
+        ExecuteMsg::UpdateStakingContract {
+            address,
+            faction,
+            staking_type,
+            active,
+        } => {
+            assert_owner_privilege(deps.as_ref(), &info)?;
+            update_staking_contract(deps, info, address, faction, staking_type, active)
+        }
+        ExecuteMsg::RemoveStakingContract { address } => {
+            assert_owner_privilege(deps.as_ref(), &info)?;
+            remove_staking_contract(deps, info, address)
+        }
+        ExecuteMsg::UpdateBondCheckpoint {
+            staker,
+            bond_amount,
+            total_bond_amount,
+        } => update_bond_checkpoint(deps, env, info, staker, bond_amount, total_bond_amount),
     }
 }
 
@@ -59,15 +95,44 @@ pub fn query(
         QueryMsg::Config {} => Ok(to_binary(&query_config(deps)?)?),
         QueryMsg::CanUserStake { user } => Ok(to_binary(&query_can_stake(deps, user)?)?),
         QueryMsg::BondAmount { user } => Ok(to_binary(&query_bond_amount(deps, user)?)?),
+        QueryMsg::BondAmounts { user } => Ok(to_binary(&query_bond_amounts(deps, user)?)?),
+        QueryMsg::BondAmountAt { user, block_time } => Ok(to_binary(&query_bond_amount_at(deps, user, block_time)?)?),
+        QueryMsg::TotalStakedAt { block_time } => Ok(to_binary(&query_total_staked_at(deps, block_time)?)?),
         QueryMsg::Addresses {} => Ok(to_binary(&query_addresses(deps)?)?),
+        QueryMsg::StakingContracts {
+            start_after,
+            limit,
+            order_by,
+        } => Ok(to_binary(&query_staking_contracts(deps, start_after, limit, order_by)?)?),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
-    _msg: MigrateMsg,
-) -> StdResult<Response> {
+    msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let legacy_staking_contracts = read_legacy_staking_contracts(deps.storage);
+    if !legacy_staking_contracts.is_empty() {
+        for contract_addr in legacy_staking_contracts.iter() {
+            let address = deps.api.addr_humanize(contract_addr)?.into_string();
+            let staking_type = match msg.staking_types.iter().find(|legacy| legacy.address == address) {
+                Some(legacy) => legacy.staking_type.clone(),
+                None => return Err(ContractError::LegacyStakingTypeMissing {}),
+            };
+            let faction = load_faction_name(deps.as_ref(), &address)?;
+            store_staking_contract(deps.storage, contract_addr, &StakingContract {
+                faction,
+                staking_type,
+                active: true,
+            })?;
+        }
+
+        // rewrite the config without the legacy staking contracts
+        let config = read_config(deps.storage)?;
+        store_config(deps.storage, &config)?;
+    }
+
     Ok(Response::default())
 }
@@ -15,12 +15,21 @@ pub enum ContractError {
     #[error("Cannot convert address to human")]
     CannotConvertAddressToHuman {},
 
-    #[error("Maximum number of staking contracts is 5")]
-    MaximumNumberOfStaking {},
-
     #[error("User cannot stake in more than one contract")]
     CannotStakeInMoreThanOneContract {},
 
     #[error("Pending owner missing")]
     PendingOwnerMissing {},
+
+    #[error("Faction weights can only be set for configured staking contracts")]
+    InvalidFactionWeights {},
+
+    #[error("Staking contract is already registered")]
+    StakingContractAlreadyRegistered {},
+
+    #[error("Staking contract is not registered")]
+    StakingContractNotRegistered {},
+
+    #[error("Staking type missing for a legacy staking contract")]
+    LegacyStakingTypeMissing {},
 }
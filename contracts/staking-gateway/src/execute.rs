@@ -1,15 +1,16 @@
-use cosmwasm_std::{ DepsMut, MessageInfo, Response};
+use cosmwasm_std::{ DepsMut, Env, MessageInfo, Response, Uint128};
 
-use crate::state::{Config, store_config, read_config, store_pending_owner, remove_pending_owner, read_pending_owner};
+use crate::state::{Config, StakingContract, store_config, read_config, store_pending_owner, remove_pending_owner, read_pending_owner, store_staking_contract, read_staking_contract, delete_staking_contract, store_bond_checkpoint, store_total_bond_checkpoint};
 use crate::errors::ContractError;
-use starterra_token::common::convert_human_to_raw;
-use crate::tools::assert_staking_contracts_len;
+use crate::tools::{assert_faction_weights, convert_faction_weights};
+use starterra_token::staking_gateway::{FactionWeight, StakingType};
 
 pub fn update_config(
     deps: DepsMut,
     info: MessageInfo,
     owner: Option<String>,
-    staking_contracts: Option<Vec<String>>,
+    multi_faction: Option<bool>,
+    faction_weights: Option<Vec<FactionWeight>>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = read_config(deps.storage)?;
     if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
@@ -20,11 +21,16 @@ pub fn update_config(
         store_pending_owner(deps.storage, &deps.api.addr_canonicalize(&owner)?)?;
     }
 
-    if let Some(staking_contracts) = staking_contracts {
-        assert_staking_contracts_len(&staking_contracts)?;
-        config.staking_contracts = convert_human_to_raw(deps.as_ref(), &staking_contracts)?;
+    if let Some(multi_faction) = multi_faction {
+        config.multi_faction = multi_faction;
     }
 
+    if let Some(faction_weights) = faction_weights {
+        config.faction_weights = convert_faction_weights(deps.as_ref(), &faction_weights)?;
+    }
+
+    assert_faction_weights(deps.as_ref(), &config)?;
+
     store_config(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "update_config"))
@@ -54,3 +60,110 @@ pub fn accept_ownership(
         .add_attribute("action", "accept_ownership")
         .add_attribute("owner", info.sender))
 }
+
+pub fn add_staking_contract(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    faction: String,
+    staking_type: StakingType,
+) -> Result<Response, ContractError> {
+    let contract_addr = deps.api.addr_canonicalize(&address)?;
+    if read_staking_contract(deps.storage, &contract_addr)?.is_some() {
+        return Err(ContractError::StakingContractAlreadyRegistered {});
+    }
+
+    store_staking_contract(deps.storage, &contract_addr, &StakingContract {
+        faction,
+        staking_type,
+        active: true,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_staking_contract")
+        .add_attribute("staking_contract", address)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn update_staking_contract(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    faction: Option<String>,
+    staking_type: Option<StakingType>,
+    active: Option<bool>,
+) -> Result<Response, ContractError> {
+    let contract_addr = deps.api.addr_canonicalize(&address)?;
+    let mut staking_contract = read_staking_contract(deps.storage, &contract_addr)?
+        .ok_or(ContractError::StakingContractNotRegistered {})?;
+
+    if let Some(faction) = faction {
+        staking_contract.faction = faction;
+    }
+
+    if let Some(staking_type) = staking_type {
+        staking_contract.staking_type = staking_type;
+    }
+
+    if let Some(active) = active {
+        staking_contract.active = active;
+    }
+
+    store_staking_contract(deps.storage, &contract_addr, &staking_contract)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_staking_contract")
+        .add_attribute("staking_contract", address)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn remove_staking_contract(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let contract_addr = deps.api.addr_canonicalize(&address)?;
+    if read_staking_contract(deps.storage, &contract_addr)?.is_none() {
+        return Err(ContractError::StakingContractNotRegistered {});
+    }
+
+    delete_staking_contract(deps.storage, &contract_addr);
+
+    // weights of removed staking contracts would otherwise linger in the config
+    let mut config: Config = read_config(deps.storage)?;
+    if config.faction_weights.iter().any(|(weighted_addr, _)| *weighted_addr == contract_addr) {
+        config.faction_weights.retain(|(weighted_addr, _)| *weighted_addr != contract_addr);
+        store_config(deps.storage, &config)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_staking_contract")
+        .add_attribute("staking_contract", address)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn update_bond_checkpoint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    staker: String,
+    bond_amount: Uint128,
+    total_bond_amount: Uint128,
+) -> Result<Response, ContractError> {
+    // only registered staking contracts report their bonds
+    let contract_addr = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if read_staking_contract(deps.storage, &contract_addr)?.is_none() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let block_time = env.block.time.seconds();
+    store_bond_checkpoint(deps.storage, &contract_addr, &deps.api.addr_canonicalize(&staker)?, block_time, &bond_amount)?;
+    store_total_bond_checkpoint(deps.storage, &contract_addr, block_time, &total_bond_amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_bond_checkpoint")
+        .add_attribute("staking_contract", info.sender)
+        .add_attribute("staker", staker)
+        .add_attribute("bond_amount", bond_amount)
+        .add_attribute("total_bond_amount", total_bond_amount))
+}
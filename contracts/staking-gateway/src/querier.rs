@@ -1,7 +1,7 @@
 use cosmwasm_std::{to_binary, CanonicalAddr, QueryRequest, StdResult, WasmQuery, Deps};
 
-use starterra_token::staking::QueryMsg::{StakerInfo};
-use starterra_token::staking::StakerInfoResponse;
+use starterra_token::staking::QueryMsg::{Config, StakerInfo};
+use starterra_token::staking::{ConfigResponse, StakerInfoResponse};
 
 pub fn load_user_staking_status(
     deps: Deps,
@@ -19,3 +19,16 @@ pub fn load_user_staking_status(
 
     return Ok((contract_addr.into(), res));
 }
+
+pub fn load_faction_name(
+    deps: Deps,
+    contract_addr: &String,
+) -> StdResult<String> {
+    let res: ConfigResponse = deps.querier
+        .query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: String::from(contract_addr),
+            msg: to_binary(&Config {})?,
+        }))?;
+
+    Ok(res.faction_name)
+}
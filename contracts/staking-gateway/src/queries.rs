@@ -1,19 +1,26 @@
 
-use starterra_token::staking_gateway::{AddressesResponse, CanStakeResponse, CanStakeStatus, ConfigResponse, BondAmountResponse};
+use starterra_token::staking_gateway::{AddressesResponse, CanStakeResponse, CanStakeStatus, ConfigResponse, BondAmountResponse, BondAmountsResponse, ContractBondResponse, FactionWeight, StakingContractResponse, StakingContractsResponse, TotalStakedResponse};
 
-use crate::state::{Config, read_config};
+use crate::state::{Config, read_config, read_active_staking_contracts, read_staking_contracts, read_bond_checkpoint, read_total_bond_checkpoint};
 use crate::errors::ContractError;
-use crate::tools::{is_user_staking, get_staking_amount, fetch_staking_statuses};
-use cosmwasm_std::{Deps, Uint128};
-use starterra_token::common::convert_raw_to_human;
+use crate::tools::{is_user_staking, get_staking_amount, fetch_staking_statuses, staking_contract_weight};
+use cosmwasm_std::{Deps, StdResult, Uint128};
+use starterra_token::common::{convert_raw_to_human, OrderBy};
 
 pub fn query_config(
     deps: Deps,
 ) -> Result<ConfigResponse, ContractError> {
     let state = read_config(deps.storage)?;
+    let faction_weights = state.faction_weights.iter()
+        .map(|(contract_addr, weight)| Ok(FactionWeight {
+            staking_contract: deps.api.addr_humanize(contract_addr)?.into_string(),
+            weight: *weight,
+        }))
+        .collect::<StdResult<Vec<FactionWeight>>>()?;
     let resp = ConfigResponse {
         owner: deps.api.addr_humanize(&state.owner)?.into_string(),
-        staking_contracts: convert_raw_to_human(deps, &state.staking_contracts)?,
+        multi_faction: state.multi_faction,
+        faction_weights,
     };
 
     Ok(resp)
@@ -26,7 +33,7 @@ pub fn query_can_stake(
     let staking_statuses = fetch_staking_statuses(deps, &user)?;
     let mut statuses = Vec::with_capacity(staking_statuses.len());
 
-    if is_user_staking(&staking_statuses) {
+    if !read_config(deps.storage)?.multi_faction && is_user_staking(&staking_statuses) {
         for s in staking_statuses {
             statuses.push(CanStakeStatus {
                 staking_contract: s.0,
@@ -56,6 +63,20 @@ pub fn query_bond_amount(
     deps: Deps,
     user: String,
 ) -> Result<BondAmountResponse, ContractError> {
+    if read_config(deps.storage)?.multi_faction {
+        let bond_amounts = query_bond_amounts(deps, user)?;
+        let contract = bond_amounts.bonds.iter()
+            .filter(|bond| !bond.bond_amount.is_zero())
+            .max_by_key(|bond| bond.bond_amount)
+            .map(|bond| bond.staking_contract.clone());
+
+        return Ok(BondAmountResponse {
+            user: bond_amounts.user,
+            contract,
+            bond_amount: bond_amounts.total_weighted_bond_amount,
+        });
+    }
+
     let staking_statuses = fetch_staking_statuses(deps, &user)?;
     let staking_info = get_staking_amount(&staking_statuses)?;
 
@@ -66,19 +87,120 @@ pub fn query_bond_amount(
     });
 }
 
-pub fn query_addresses(
+pub fn query_bond_amounts(
     deps: Deps,
-) -> Result<AddressesResponse, ContractError> {
+    user: String,
+) -> Result<BondAmountsResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let bonds = fetch_staking_statuses(deps, &user)?
+        .into_iter()
+        .map(|(staking_contract, staker_info)| Ok(ContractBondResponse {
+            weight: staking_contract_weight(&config, &deps.api.addr_canonicalize(&staking_contract)?),
+            staking_contract,
+            bond_amount: staker_info.bond_amount,
+        }))
+        .collect::<StdResult<Vec<ContractBondResponse>>>()?;
+    let total_bond_amount = bonds.iter().map(|bond| bond.bond_amount).sum();
+    let total_weighted_bond_amount = bonds.iter().map(|bond| bond.bond_amount * bond.weight).sum();
+
+    Ok(BondAmountsResponse {
+        user,
+        bonds,
+        total_bond_amount,
+        total_weighted_bond_amount,
+    })
+}
+
+pub fn query_bond_amount_at(
+    deps: Deps,
+    user: String,
+    block_time: u64,
+) -> Result<BondAmountResponse, ContractError> {
     let config: Config = read_config(deps.storage)?;
+    let raw_user = deps.api.addr_canonicalize(&user)?;
+    let mut contract: Option<String> = None;
+    let mut contract_bond_amount = Uint128::zero();
+    let mut bond_amount = Uint128::zero();
+    for contract_addr in read_active_staking_contracts(deps.storage)? {
+        let checkpoint_bond_amount = read_bond_checkpoint(deps.storage, &contract_addr, &raw_user, block_time)?;
+        if checkpoint_bond_amount.is_zero() {
+            continue;
+        }
 
-    let addresses: Vec<String> = config.staking_contracts
-        .iter()
-        .map(|contract_addr| {
-            let addr = deps.api.addr_humanize(contract_addr);
-            addr.unwrap().into_string()
-        }).collect::<Vec<String>>();
+        // same rules as BondAmount, in multi faction mode bond_amount is the total weighted stake
+        if config.multi_faction {
+            bond_amount += checkpoint_bond_amount * staking_contract_weight(&config, &contract_addr);
+        } else if contract.is_some() {
+            return Err(ContractError::CannotStakeInMoreThanOneContract {});
+        } else {
+            bond_amount = checkpoint_bond_amount;
+        }
+
+        if checkpoint_bond_amount >= contract_bond_amount {
+            contract_bond_amount = checkpoint_bond_amount;
+            contract = Some(deps.api.addr_humanize(&contract_addr)?.into_string());
+        }
+    }
+
+    Ok(BondAmountResponse {
+        user,
+        contract,
+        bond_amount,
+    })
+}
+
+pub fn query_total_staked_at(
+    deps: Deps,
+    block_time: u64,
+) -> Result<TotalStakedResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut total_bond_amount = Uint128::zero();
+    let mut total_weighted_bond_amount = Uint128::zero();
+    for contract_addr in read_active_staking_contracts(deps.storage)? {
+        let bond_amount = read_total_bond_checkpoint(deps.storage, &contract_addr, block_time)?;
+        total_bond_amount += bond_amount;
+        total_weighted_bond_amount += bond_amount * staking_contract_weight(&config, &contract_addr);
+    }
+
+    Ok(TotalStakedResponse {
+        block_time,
+        total_bond_amount,
+        total_weighted_bond_amount,
+    })
+}
+
+pub fn query_addresses(
+    deps: Deps,
+) -> Result<AddressesResponse, ContractError> {
+    let addresses = convert_raw_to_human(deps, &read_active_staking_contracts(deps.storage)?)?;
 
     return Ok(AddressesResponse {
         addresses
     });
 }
+
+pub fn query_staking_contracts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> Result<StakingContractsResponse, ContractError> {
+    let start_after = match start_after {
+        Some(start_after) => Some(deps.api.addr_canonicalize(&start_after)?),
+        None => None,
+    };
+
+    let staking_contracts = read_staking_contracts(deps.storage, start_after, limit, order_by)?
+        .into_iter()
+        .map(|(contract_addr, staking_contract)| Ok(StakingContractResponse {
+            address: deps.api.addr_humanize(&contract_addr)?.into_string(),
+            faction: staking_contract.faction,
+            staking_type: staking_contract.staking_type,
+            active: staking_contract.active,
+        }))
+        .collect::<StdResult<Vec<StakingContractResponse>>>()?;
+
+    Ok(StakingContractsResponse {
+        staking_contracts,
+    })
+}
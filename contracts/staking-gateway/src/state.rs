@@ -1,16 +1,25 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
-use cosmwasm_storage::{singleton, singleton_read, Singleton};
+use cosmwasm_std::{CanonicalAddr, Decimal, Order, StdResult, Storage, Uint128};
+use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket, Singleton};
+use starterra_token::common::OrderBy;
+use starterra_token::staking_gateway::StakingType;
 
 static KEY_CONFIG: &[u8] = b"config";
 static KEY_PENDING_OWNER: &[u8] = b"pending_owner";
 
+static PREFIX_KEY_STAKING_CONTRACT: &[u8] = b"staking_contract";
+static PREFIX_KEY_BOND_CHECKPOINT: &[u8] = b"bond_checkpoint";
+static PREFIX_KEY_TOTAL_BOND_CHECKPOINT: &[u8] = b"total_bond_checkpoint";
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: CanonicalAddr,
-    pub staking_contracts: Vec<CanonicalAddr>,
+    #[serde(default)]
+    pub multi_faction: bool,
+    #[serde(default)]
+    pub faction_weights: Vec<(CanonicalAddr, Decimal)>,
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -21,6 +30,147 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     singleton_read(storage, KEY_CONFIG).load()
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct LegacyConfig {
+    staking_contracts: Vec<CanonicalAddr>,
+}
+
+// the staking contracts kept in the config before the staking contract registry existed
+pub fn read_legacy_staking_contracts(storage: &dyn Storage) -> Vec<CanonicalAddr> {
+    singleton_read::<LegacyConfig>(storage, KEY_CONFIG).load().ok()
+        .map_or(vec![], |config| config.staking_contracts)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakingContract {
+    pub faction: String,
+    pub staking_type: StakingType,
+    pub active: bool,
+}
+
+pub fn store_staking_contract(
+    storage: &mut dyn Storage,
+    contract_addr: &CanonicalAddr,
+    staking_contract: &StakingContract,
+) -> StdResult<()> {
+    Bucket::new(storage, PREFIX_KEY_STAKING_CONTRACT).save(contract_addr.as_slice(), staking_contract)
+}
+
+pub fn read_staking_contract(storage: &dyn Storage, contract_addr: &CanonicalAddr) -> StdResult<Option<StakingContract>> {
+    ReadonlyBucket::new(storage, PREFIX_KEY_STAKING_CONTRACT).may_load(contract_addr.as_slice())
+}
+
+pub fn delete_staking_contract(storage: &mut dyn Storage, contract_addr: &CanonicalAddr) {
+    Bucket::<StakingContract>::new(storage, PREFIX_KEY_STAKING_CONTRACT).remove(contract_addr.as_slice());
+}
+
+pub fn read_active_staking_contracts(storage: &dyn Storage) -> StdResult<Vec<CanonicalAddr>> {
+    let bucket: ReadonlyBucket<StakingContract> = ReadonlyBucket::new(storage, PREFIX_KEY_STAKING_CONTRACT);
+    bucket
+        .range(None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((k, staking_contract)) if staking_contract.active => Some(Ok(CanonicalAddr::from(k))),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .collect()
+}
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+pub fn read_staking_contracts(
+    storage: &dyn Storage,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+) -> StdResult<Vec<(CanonicalAddr, StakingContract)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let (start, end, order_by) = match order_by {
+        Some(OrderBy::Asc) => (calc_range_start_addr(start_after), None, OrderBy::Asc),
+        _ => (None, calc_range_end_addr(start_after), OrderBy::Desc),
+    };
+
+    let bucket: ReadonlyBucket<StakingContract> = ReadonlyBucket::new(storage, PREFIX_KEY_STAKING_CONTRACT);
+    bucket
+        .range(start.as_deref(), end.as_deref(), order_by.into())
+        .take(limit)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_bond_checkpoint(
+    storage: &mut dyn Storage,
+    contract_addr: &CanonicalAddr,
+    staker: &CanonicalAddr,
+    block_time: u64,
+    bond_amount: &Uint128,
+) -> StdResult<()> {
+    Bucket::multilevel(storage, &[PREFIX_KEY_BOND_CHECKPOINT, contract_addr.as_slice(), staker.as_slice()])
+        .save(&block_time.to_be_bytes(), bond_amount)
+}
+
+pub fn read_bond_checkpoint(
+    storage: &dyn Storage,
+    contract_addr: &CanonicalAddr,
+    staker: &CanonicalAddr,
+    block_time: u64,
+) -> StdResult<Uint128> {
+    read_latest_checkpoint(
+        ReadonlyBucket::multilevel(storage, &[PREFIX_KEY_BOND_CHECKPOINT, contract_addr.as_slice(), staker.as_slice()]),
+        block_time,
+    )
+}
+
+pub fn store_total_bond_checkpoint(
+    storage: &mut dyn Storage,
+    contract_addr: &CanonicalAddr,
+    block_time: u64,
+    total_bond_amount: &Uint128,
+) -> StdResult<()> {
+    Bucket::multilevel(storage, &[PREFIX_KEY_TOTAL_BOND_CHECKPOINT, contract_addr.as_slice()])
+        .save(&block_time.to_be_bytes(), total_bond_amount)
+}
+
+pub fn read_total_bond_checkpoint(
+    storage: &dyn Storage,
+    contract_addr: &CanonicalAddr,
+    block_time: u64,
+) -> StdResult<Uint128> {
+    read_latest_checkpoint(
+        ReadonlyBucket::multilevel(storage, &[PREFIX_KEY_TOTAL_BOND_CHECKPOINT, contract_addr.as_slice()]),
+        block_time,
+    )
+}
+
+// the amount of the latest checkpoint at or before the block time, zero when there is none
+fn read_latest_checkpoint(bucket: ReadonlyBucket<Uint128>, block_time: u64) -> StdResult<Uint128> {
+    // range ends are exclusive, appending a byte keeps the checkpoint at the block time itself
+    let mut end = block_time.to_be_bytes().to_vec();
+    end.push(0);
+
+    match bucket.range(None, Some(&end), Order::Descending).next() {
+        Some(item) => Ok(item?.1),
+        None => Ok(Uint128::zero()),
+    }
+}
+
+// this will set the first key after the provided key, by appending a 1 byte
+fn calc_range_start_addr(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
+    start_after.map(|addr| {
+        let mut v = addr.as_slice().to_vec();
+        v.push(1);
+        v
+    })
+}
+
+// range ends are exclusive, so the provided key itself is skipped
+fn calc_range_end_addr(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
+    start_after.map(|addr| addr.as_slice().to_vec())
+}
+
 pub fn store_pending_owner(storage: &mut dyn Storage, new_owner: &CanonicalAddr) -> StdResult<()> {
     singleton(storage, KEY_PENDING_OWNER).save(new_owner)
 }
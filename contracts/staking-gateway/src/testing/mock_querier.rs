@@ -2,7 +2,7 @@ use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADD
 use cosmwasm_std::{from_slice, to_binary, Coin, Empty, Querier, QuerierResult, QueryRequest, SystemError, WasmQuery, OwnedDeps, SystemResult, ContractResult, from_binary, Decimal, Uint128};
 use std::collections::HashMap;
 
-use starterra_token::staking::{StakerInfo, StakerInfoResponse};
+use starterra_token::staking::{ConfigResponse, StakerInfo, StakerInfoResponse, SubmitToUnbondConfig};
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
 /// this uses our CustomQuerier.
@@ -28,6 +28,7 @@ pub struct WasmMockQuerier {
 pub struct TokenQuerier {
     // this lets us iterate over all pairs that match the first string
     staker_info: HashMap<String, HashMap<String, StakerInfo>>,
+    faction_names: HashMap<String, String>,
 }
 
 pub(crate) fn data_to_map<T>(
@@ -117,7 +118,29 @@ impl WasmMockQuerier {
                             submit_to_unbond_info: None,
                         })))
                     }
-                    _ => self.base.handle_query(request),                }
+                    starterra_token::staking::QueryMsg::Config {} => {
+                        let faction_name = match self.token_querier.faction_names.get(contract_addr) {
+                            Some(faction_name) => faction_name.clone(),
+                            None => return SystemResult::Err(SystemError::NoSuchContract { addr: contract_addr.clone() }),
+                        };
+
+                        SystemResult::Ok(ContractResult::from(to_binary(&ConfigResponse {
+                            owner: String::from("owner"),
+                            starterra_token: String::from("starterra_token"),
+                            staking_token: String::from("staking_token"),
+                            burn_address: String::from("burn"),
+                            gateway_address: String::from(MOCK_CONTRACT_ADDR),
+                            distribution_schedule: vec![],
+                            faction_name,
+                            paused: false,
+                            max_pending_unbond_count: 0,
+                            fee_configuration: vec![],
+                            unbond_config: vec![],
+                            submit_to_unbond_config: SubmitToUnbondConfig { lock_time: 0 },
+                        })))
+                    }
+                    _ => self.base.handle_query(request),
+                }
             }
             _ => self.base.handle_query(request),
         }
@@ -136,4 +159,10 @@ impl WasmMockQuerier {
     pub fn with_staker_info(&mut self, staker_info: Vec<(String, Vec<(String, StakerInfo)>)>) {
         self.token_querier.staker_info = data_to_map(staker_info);
     }
+
+    pub fn with_faction_names(&mut self, faction_names: &[(&str, &str)]) {
+        self.token_querier.faction_names = faction_names.iter()
+            .map(|(contract_addr, faction_name)| (contract_addr.to_string(), faction_name.to_string()))
+            .collect();
+    }
 }
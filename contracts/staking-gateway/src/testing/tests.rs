@@ -1,13 +1,23 @@
-use cosmwasm_std::{from_binary, Uint128, Timestamp, attr};
+use cosmwasm_std::{from_binary, Uint128, Timestamp, attr, Api, CanonicalAddr, Decimal, Deps};
+use cosmwasm_storage::singleton;
 use cosmwasm_std::testing::{mock_env, mock_info};
 
-use starterra_token::staking_gateway::{CanStakeResponse, CanStakeStatus, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, BondAmountResponse};
+use starterra_token::staking_gateway::{CanStakeResponse, CanStakeStatus, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, BondAmountResponse, BondAmountsResponse, ContractBondResponse, FactionWeight, StakingContractInfo, StakingType, StakingContractResponse, StakingContractsResponse, AddressesResponse, MigrateMsg, LegacyStakingContract, TotalStakedResponse};
+use starterra_token::common::OrderBy;
 use starterra_token::staking::StakerInfo;
 
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query};
 use crate::testing::mock_querier::mock_dependencies;
 use crate::errors::ContractError;
 
+fn staking_contract_infos(addresses: &[&str]) -> Vec<StakingContractInfo> {
+    addresses.iter().map(|address| StakingContractInfo {
+        address: address.to_string(),
+        faction: String::from("faction"),
+        staking_type: StakingType::Stt,
+    }).collect()
+}
+
 #[test]
 fn proper_initialization() {
     let mut deps = mock_dependencies(&[]);
@@ -16,7 +26,9 @@ fn proper_initialization() {
     let info = mock_info("addr0000", &[]);
     let msg = InstantiateMsg {
         owner: String::from("owner"),
-        staking_contracts: vec![String::from("addr1"), String::from("addr2")],
+        staking_contracts: staking_contract_infos(&["addr1", "addr2"]),
+        multi_faction: false,
+        faction_weights: vec![],
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -29,7 +41,8 @@ fn proper_initialization() {
         config,
         ConfigResponse {
             owner: String::from("owner"),
-            staking_contracts: vec![String::from("addr1"), String::from("addr2")],
+            multi_faction: false,
+            faction_weights: vec![],
         }
     );
 }
@@ -42,7 +55,9 @@ fn update_config() {
     let info = mock_info("addr0000", &[]);
     let msg = InstantiateMsg {
         owner: String::from("owner0000"),
-        staking_contracts: vec![String::from("addr1")],
+        staking_contracts: staking_contract_infos(&["addr1"]),
+        multi_faction: false,
+        faction_weights: vec![],
     };
     let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -53,7 +68,8 @@ fn update_config() {
     let pause_block_time = env.block.time.seconds();
     let msg = ExecuteMsg::UpdateConfig {
         owner: Some(String::from("owner0001")),
-        staking_contracts: None,
+        multi_faction: None,
+        faction_weights: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
@@ -70,7 +86,11 @@ fn update_config() {
     // Unauthorized err
     let env = mock_env();
     let info = mock_info("owner0000", &[]);
-    let msg = ExecuteMsg::UpdateConfig { owner: None, staking_contracts: None };
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        multi_faction: None,
+        faction_weights: None,
+    };
 
     let res = execute(deps.as_mut(), env.clone(), info, msg);
     match res {
@@ -84,7 +104,8 @@ fn update_config() {
         config,
         ConfigResponse {
             owner: String::from("owner0001"),
-            staking_contracts: vec![String::from("addr1")],
+            multi_faction: false,
+            faction_weights: vec![],
         }
     );
 
@@ -93,7 +114,8 @@ fn update_config() {
     env.block.time = Timestamp::from_seconds(pause_block_time + 50);
     let msg = ExecuteMsg::UpdateConfig {
         owner: None,
-        staking_contracts: Some(vec![String::from("addr1"), String::from("addr2")]),
+        multi_faction: Some(true),
+        faction_weights: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -103,7 +125,8 @@ fn update_config() {
         config,
         ConfigResponse {
             owner: String::from("owner0001"),
-            staking_contracts: vec![String::from("addr1"), String::from("addr2")],
+            multi_faction: true,
+            faction_weights: vec![],
         }
     );
 }
@@ -116,6 +139,8 @@ fn accept_ownership() {
     let msg = InstantiateMsg {
         owner: String::from("owner0000"),
         staking_contracts: vec![],
+        multi_faction: false,
+        faction_weights: vec![],
     };
     let info = mock_info("addr0000", &vec![]);
     let _res = instantiate(deps.as_mut(), env, info.clone(), msg).unwrap();
@@ -131,7 +156,8 @@ fn accept_ownership() {
 
     let msg = ExecuteMsg::UpdateConfig {
         owner: Some(String::from("new_owner")),
-        staking_contracts: None
+        multi_faction: None,
+        faction_weights: None,
     };
     let info = mock_info("owner0000", &vec![]);
     let env = mock_env();
@@ -167,7 +193,9 @@ fn test_querier() {
     let info = mock_info("addr0000", &[]);
     let msg = InstantiateMsg {
         owner: String::from("owner0000"),
-        staking_contracts: vec![String::from("staking0000"), String::from("staking0001"), String::from("staking0002")],
+        staking_contracts: staking_contract_infos(&["staking0000", "staking0001", "staking0002"]),
+        multi_faction: false,
+        faction_weights: vec![],
     };
     let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -179,9 +207,10 @@ fn test_querier() {
     assert_eq!(
         can_stake,
         CanStakeResponse {
-            statuses: vec![CanStakeStatus { staking_contract: String::from("staking0000"), can_stake: true },
-                           CanStakeStatus { staking_contract: String::from("staking0001"), can_stake: true },
-                           CanStakeStatus { staking_contract: String::from("staking0002"), can_stake: true }],
+            // ordered by canonical address
+            statuses: vec![CanStakeStatus { staking_contract: String::from("staking0002"), can_stake: true },
+                           CanStakeStatus { staking_contract: String::from("staking0000"), can_stake: true },
+                           CanStakeStatus { staking_contract: String::from("staking0001"), can_stake: true }],
         }
     );
 
@@ -218,9 +247,10 @@ fn test_querier() {
     assert_eq!(
         can_stake,
         CanStakeResponse {
-            statuses: vec![CanStakeStatus { staking_contract: String::from("staking0000"), can_stake: false },
-                           CanStakeStatus { staking_contract: String::from("staking0001"), can_stake: true },
-                           CanStakeStatus { staking_contract: String::from("staking0002"), can_stake: false }],
+            // ordered by canonical address
+            statuses: vec![CanStakeStatus { staking_contract: String::from("staking0002"), can_stake: false },
+                           CanStakeStatus { staking_contract: String::from("staking0000"), can_stake: false },
+                           CanStakeStatus { staking_contract: String::from("staking0001"), can_stake: true }],
         }
     );
 
@@ -277,3 +307,460 @@ fn test_querier() {
         _ => panic!("WRONG ERROR MSG"),
     }
 }
+
+#[test]
+fn multi_faction_staking() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // weights for unknown staking contracts are rejected
+    let msg = InstantiateMsg {
+        owner: String::from("owner0000"),
+        staking_contracts: staking_contract_infos(&["staking0000", "staking0001"]),
+        multi_faction: true,
+        faction_weights: vec![FactionWeight { staking_contract: String::from("staking0002"), weight: Decimal::percent(150) }],
+    };
+    let res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidFactionWeights {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner0000"),
+        staking_contracts: staking_contract_infos(&["staking0000", "staking0001", "staking0002"]),
+        multi_faction: false,
+        faction_weights: vec![],
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier.with_staker_info(vec![(
+        String::from("staking0001"),
+        vec![(
+            String::from("user0000"),
+            StakerInfo {
+                reward_index: Default::default(),
+                bond_amount: Uint128::from(100u64),
+                pending_reward: Default::default(),
+            }
+        )],
+    ), (
+        String::from("staking0002"),
+        vec![(
+            String::from("user0000"),
+            StakerInfo {
+                reward_index: Default::default(),
+                bond_amount: Uint128::from(125u64),
+                pending_reward: Default::default(),
+            }
+        )],
+    ),
+    ]);
+
+    // per contract breakdown is available in single contract mode as well
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::BondAmounts {
+        user: String::from("user0000"),
+    }).unwrap();
+    let bond_amounts: BondAmountsResponse = from_binary(&res).unwrap();
+    assert_eq!(bond_amounts.total_bond_amount, Uint128::from(225u64));
+    assert_eq!(bond_amounts.total_weighted_bond_amount, Uint128::from(225u64));
+
+    // switch to multi faction mode and weight staking0002 higher
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        multi_faction: Some(true),
+        faction_weights: Some(vec![FactionWeight { staking_contract: String::from("staking0002"), weight: Decimal::percent(200) }]),
+    };
+    let info = mock_info("owner0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert!(config.multi_faction);
+    assert_eq!(
+        config.faction_weights,
+        vec![FactionWeight { staking_contract: String::from("staking0002"), weight: Decimal::percent(200) }]
+    );
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::CanUserStake {
+        user: String::from("user0000"),
+    }).unwrap();
+    let can_stake: CanStakeResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        can_stake,
+        CanStakeResponse {
+            // ordered by canonical address
+            statuses: vec![CanStakeStatus { staking_contract: String::from("staking0002"), can_stake: true },
+                           CanStakeStatus { staking_contract: String::from("staking0000"), can_stake: true },
+                           CanStakeStatus { staking_contract: String::from("staking0001"), can_stake: true }],
+        }
+    );
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::BondAmounts {
+        user: String::from("user0000"),
+    }).unwrap();
+    let bond_amounts: BondAmountsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        bond_amounts,
+        BondAmountsResponse {
+            user: String::from("user0000"),
+            // ordered by canonical address
+            bonds: vec![
+                ContractBondResponse { staking_contract: String::from("staking0002"), bond_amount: Uint128::from(125u64), weight: Decimal::percent(200) },
+                ContractBondResponse { staking_contract: String::from("staking0000"), bond_amount: Uint128::zero(), weight: Decimal::one() },
+                ContractBondResponse { staking_contract: String::from("staking0001"), bond_amount: Uint128::from(100u64), weight: Decimal::one() },
+            ],
+            total_bond_amount: Uint128::from(225u64),
+            total_weighted_bond_amount: Uint128::from(350u64),
+        }
+    );
+
+    // bond amount reports the weighted total and the contract with the largest bond
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::BondAmount {
+        user: String::from("user0000"),
+    }).unwrap();
+    let bond_amount: BondAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        bond_amount,
+        BondAmountResponse {
+            user: String::from("user0000"),
+            contract: Some(String::from("staking0002")),
+            bond_amount: Uint128::from(350u64),
+        }
+    );
+
+    // removing a weighted staking contract drops its weight
+    let msg = ExecuteMsg::RemoveStakingContract {
+        address: String::from("staking0002"),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.faction_weights, vec![]);
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::BondAmount {
+        user: String::from("user0000"),
+    }).unwrap();
+    let bond_amount: BondAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(bond_amount.contract, Some(String::from("staking0001")));
+    assert_eq!(bond_amount.bond_amount, Uint128::from(100u64));
+}
+
+#[test]
+fn staking_contract_registry() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let msg = InstantiateMsg {
+        owner: String::from("owner0000"),
+        staking_contracts: staking_contract_infos(&["staking0000", "staking0001", "staking0002", "staking0003", "staking0004"]),
+        multi_faction: true,
+        faction_weights: vec![],
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // more than five staking contracts can be registered
+    let info = mock_info("owner0000", &[]);
+    for address in ["staking0005", "staking0006"] {
+        let msg = ExecuteMsg::AddStakingContract {
+            address: String::from(address),
+            faction: String::from("lp faction"),
+            staking_type: StakingType::Lp,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "add_staking_contract"),
+                attr("staking_contract", address),
+                attr("sender", "owner0000"),
+            ]
+        );
+    }
+
+    let msg = ExecuteMsg::AddStakingContract {
+        address: String::from("staking0006"),
+        faction: String::from("lp faction"),
+        staking_type: StakingType::Lp,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::StakingContractAlreadyRegistered {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::AddStakingContract {
+        address: String::from("staking0007"),
+        faction: String::from("lp faction"),
+        staking_type: StakingType::Lp,
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("attacker", &[]), msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // paginate through the registry
+    let query_page = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, start_after: Option<String>| {
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::StakingContracts {
+            start_after,
+            limit: Some(4),
+            order_by: Some(OrderBy::Asc),
+        }).unwrap();
+        let staking_contracts: StakingContractsResponse = from_binary(&res).unwrap();
+        staking_contracts.staking_contracts
+    };
+    let first_page = query_page(&deps, None);
+    assert_eq!(first_page.len(), 4);
+    let second_page = query_page(&deps, Some(first_page[3].address.clone()));
+    assert_eq!(second_page.len(), 3);
+    let mut addresses: Vec<String> = first_page.iter().chain(second_page.iter())
+        .map(|staking_contract| staking_contract.address.clone())
+        .collect();
+    addresses.sort();
+    assert_eq!(addresses, vec!["staking0000", "staking0001", "staking0002", "staking0003", "staking0004", "staking0005", "staking0006"]);
+    assert!(second_page.iter().chain(first_page.iter()).any(|staking_contract| *staking_contract == StakingContractResponse {
+        address: String::from("staking0006"),
+        faction: String::from("lp faction"),
+        staking_type: StakingType::Lp,
+        active: true,
+    }));
+
+    deps.querier.with_staker_info(vec![(
+        String::from("staking0006"),
+        vec![(
+            String::from("user0000"),
+            StakerInfo {
+                reward_index: Default::default(),
+                bond_amount: Uint128::from(100u64),
+                pending_reward: Default::default(),
+            }
+        )],
+    )]);
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::BondAmount {
+        user: String::from("user0000"),
+    }).unwrap();
+    let bond_amount: BondAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(bond_amount.bond_amount, Uint128::from(100u64));
+
+    // inactive staking contracts are skipped
+    let msg = ExecuteMsg::UpdateStakingContract {
+        address: String::from("staking0006"),
+        faction: Some(String::from("renamed faction")),
+        staking_type: None,
+        active: Some(false),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::BondAmount {
+        user: String::from("user0000"),
+    }).unwrap();
+    let bond_amount: BondAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(bond_amount.bond_amount, Uint128::zero());
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Addresses {}).unwrap();
+    let addresses: AddressesResponse = from_binary(&res).unwrap();
+    assert_eq!(addresses.addresses.len(), 6);
+    assert!(!addresses.addresses.contains(&String::from("staking0006")));
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::StakingContracts {
+        start_after: Some(String::from("staking0006")),
+        limit: None,
+        order_by: None,
+    }).unwrap();
+    let staking_contracts: StakingContractsResponse = from_binary(&res).unwrap();
+    assert!(!staking_contracts.staking_contracts.iter().any(|staking_contract| staking_contract.address == "staking0006"));
+
+    let msg = ExecuteMsg::UpdateStakingContract {
+        address: String::from("staking0007"),
+        faction: None,
+        staking_type: None,
+        active: Some(true),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::StakingContractNotRegistered {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::RemoveStakingContract {
+        address: String::from("staking0006"),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(query_page(&deps, None).len(), 4);
+    assert_eq!(query_page(&deps, Some(query_page(&deps, None)[3].address.clone())).len(), 2);
+}
+
+#[test]
+fn migrate_legacy_staking_contracts() {
+    let mut deps = mock_dependencies(&[]);
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct LegacyConfig {
+        owner: CanonicalAddr,
+        staking_contracts: Vec<CanonicalAddr>,
+    }
+    singleton(&mut deps.storage, b"config").save(&LegacyConfig {
+        owner: deps.api.addr_canonicalize("owner0000").unwrap(),
+        staking_contracts: vec![
+            deps.api.addr_canonicalize("staking0000").unwrap(),
+            deps.api.addr_canonicalize("staking0001").unwrap(),
+        ],
+    }).unwrap();
+
+    deps.querier.with_faction_names(&[("staking0000", "a"), ("staking0001", "b")]);
+
+    // every legacy staking contract needs its staking type
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {
+        staking_types: vec![LegacyStakingContract {
+            address: String::from("staking0000"),
+            staking_type: StakingType::Lp,
+        }],
+    });
+    match res {
+        Err(ContractError::LegacyStakingTypeMissing {}) => {}
+        _ => panic!("Must return legacy staking type missing error"),
+    }
+
+    let msg = MigrateMsg {
+        staking_types: vec![
+            LegacyStakingContract {
+                address: String::from("staking0000"),
+                staking_type: StakingType::Lp,
+            },
+            LegacyStakingContract {
+                address: String::from("staking0001"),
+                staking_type: StakingType::Stt,
+            },
+        ],
+    };
+    let _res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.owner, "owner0000");
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::StakingContracts {
+        start_after: None,
+        limit: None,
+        order_by: Some(OrderBy::Asc),
+    }).unwrap();
+    let staking_contracts: StakingContractsResponse = from_binary(&res).unwrap();
+    assert_eq!(staking_contracts.staking_contracts.len(), 2);
+    assert!(staking_contracts.staking_contracts.contains(&StakingContractResponse {
+        address: String::from("staking0000"),
+        faction: String::from("a"),
+        staking_type: StakingType::Lp,
+        active: true,
+    }));
+    assert!(staking_contracts.staking_contracts.contains(&StakingContractResponse {
+        address: String::from("staking0001"),
+        faction: String::from("b"),
+        staking_type: StakingType::Stt,
+        active: true,
+    }));
+
+    // migrating again keeps the registry intact
+    let _res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Addresses {}).unwrap();
+    let addresses: AddressesResponse = from_binary(&res).unwrap();
+    assert_eq!(addresses.addresses.len(), 2);
+}
+
+#[test]
+fn bond_checkpoints() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let msg = InstantiateMsg {
+        owner: String::from("owner0000"),
+        staking_contracts: staking_contract_infos(&["staking0000", "staking0001"]),
+        multi_faction: false,
+        faction_weights: vec![FactionWeight { staking_contract: String::from("staking0001"), weight: Decimal::percent(200) }],
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let checkpoint_msg = |staker: &str, bond_amount: u64, total_bond_amount: u64| ExecuteMsg::UpdateBondCheckpoint {
+        staker: String::from(staker),
+        bond_amount: Uint128::from(bond_amount),
+        total_bond_amount: Uint128::from(total_bond_amount),
+    };
+
+    // only registered staking contracts can write checkpoints
+    let res = execute(deps.as_mut(), env.clone(), mock_info("user0000", &[]), checkpoint_msg("user0000", 100, 100));
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.time = Timestamp::from_seconds(1_000);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("staking0000", &[]), checkpoint_msg("user0000", 100, 100)).unwrap();
+    assert_eq!(res.attributes, vec![
+        attr("action", "update_bond_checkpoint"),
+        attr("staking_contract", "staking0000"),
+        attr("staker", "user0000"),
+        attr("bond_amount", "100"),
+        attr("total_bond_amount", "100"),
+    ]);
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("staking0001", &[]), checkpoint_msg("user0001", 50, 50)).unwrap();
+
+    env.block.time = Timestamp::from_seconds(2_000);
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("staking0000", &[]), checkpoint_msg("user0000", 40, 90)).unwrap();
+
+    let bond_amount_at = |deps: Deps, user: &str, block_time: u64| -> BondAmountResponse {
+        from_binary(&query(deps, mock_env(), QueryMsg::BondAmountAt { user: String::from(user), block_time }).unwrap()).unwrap()
+    };
+
+    // nothing is bonded before the first checkpoint
+    let bond_amount = bond_amount_at(deps.as_ref(), "user0000", 999);
+    assert_eq!(bond_amount.contract, None);
+    assert_eq!(bond_amount.bond_amount, Uint128::zero());
+
+    let bond_amount = bond_amount_at(deps.as_ref(), "user0000", 1_000);
+    assert_eq!(bond_amount.contract, Some(String::from("staking0000")));
+    assert_eq!(bond_amount.bond_amount, Uint128::from(100u64));
+
+    let bond_amount = bond_amount_at(deps.as_ref(), "user0000", 1_999);
+    assert_eq!(bond_amount.bond_amount, Uint128::from(100u64));
+
+    let bond_amount = bond_amount_at(deps.as_ref(), "user0000", 5_000);
+    assert_eq!(bond_amount.bond_amount, Uint128::from(40u64));
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::TotalStakedAt { block_time: 1_500 }).unwrap();
+    let total_staked: TotalStakedResponse = from_binary(&res).unwrap();
+    assert_eq!(total_staked, TotalStakedResponse {
+        block_time: 1_500,
+        total_bond_amount: Uint128::from(150u64),
+        total_weighted_bond_amount: Uint128::from(200u64),
+    });
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::TotalStakedAt { block_time: 2_000 }).unwrap();
+    let total_staked: TotalStakedResponse = from_binary(&res).unwrap();
+    assert_eq!(total_staked.total_bond_amount, Uint128::from(140u64));
+    assert_eq!(total_staked.total_weighted_bond_amount, Uint128::from(190u64));
+
+    // a bond in a second contract is rejected in single faction mode and weighted in multi faction mode
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("staking0001", &[]), checkpoint_msg("user0000", 30, 80)).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::BondAmountAt { user: String::from("user0000"), block_time: 2_000 });
+    match res {
+        Err(ContractError::CannotStakeInMoreThanOneContract {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        multi_faction: Some(true),
+        faction_weights: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let bond_amount = bond_amount_at(deps.as_ref(), "user0000", 2_000);
+    assert_eq!(bond_amount.contract, Some(String::from("staking0000")));
+    assert_eq!(bond_amount.bond_amount, Uint128::from(100u64));
+}
@@ -1,18 +1,18 @@
 use starterra_token::staking::StakerInfoResponse;
 
 use crate::querier::load_user_staking_status;
-use crate::state::{Config, read_config};
+use crate::state::{Config, read_config, read_active_staking_contracts, read_staking_contract};
 use crate::errors::ContractError;
-use cosmwasm_std::{Deps, Uint128, StdResult, StdError};
+use cosmwasm_std::{Deps, Uint128, StdResult, Decimal, CanonicalAddr, MessageInfo};
+use starterra_token::staking_gateway::FactionWeight;
 
 pub fn fetch_staking_statuses(
     deps: Deps,
     account_addr: &String,
 ) -> StdResult<Vec<(String, StakerInfoResponse)>> {
-    let config: Config = read_config(deps.storage)?;
     let raw_addr = deps.api.addr_canonicalize(account_addr)?;
 
-    config.staking_contracts.iter().map(|contract_addr| {
+    read_active_staking_contracts(deps.storage)?.iter().map(|contract_addr| {
         load_user_staking_status(deps, &deps.api.addr_humanize(contract_addr)?.into_string(), &raw_addr)
     }).collect::<StdResult<Vec<(String, StakerInfoResponse)>>>()
 }
@@ -48,10 +48,36 @@ pub fn get_staking_amount(
     return Ok((contract_addr, staking_amount));
 }
 
-pub fn assert_staking_contracts_len(staking_contracts: &Vec<String>) -> StdResult<()>
-{
-    if staking_contracts.len() > 5 {
-        return Err(StdError::generic_err("Maximum number of staking contracts is 5"));
+pub fn assert_owner_privilege(deps: Deps, info: &MessageInfo) -> Result<(), ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+pub fn convert_faction_weights(
+    deps: Deps,
+    faction_weights: &[FactionWeight],
+) -> StdResult<Vec<(CanonicalAddr, Decimal)>> {
+    faction_weights.iter().map(|faction_weight| {
+        Ok((deps.api.addr_canonicalize(&faction_weight.staking_contract)?, faction_weight.weight))
+    }).collect()
+}
+
+pub fn assert_faction_weights(deps: Deps, config: &Config) -> Result<(), ContractError> {
+    for (contract_addr, _) in config.faction_weights.iter() {
+        if read_staking_contract(deps.storage, contract_addr)?.is_none() {
+            return Err(ContractError::InvalidFactionWeights {});
+        }
     }
+
     Ok(())
 }
+
+pub fn staking_contract_weight(config: &Config, contract_addr: &CanonicalAddr) -> Decimal {
+    config.faction_weights.iter()
+        .find(|(weighted_addr, _)| weighted_addr == contract_addr)
+        .map_or(Decimal::one(), |(_, weight)| *weight)
+}
@@ -1,6 +1,8 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use cosmwasm_std::{Uint128};
+use cosmwasm_std::{Decimal, Uint128};
+use cw20::Cw20ReceiveMsg;
+use terraswap::router::SwapOperation;
 use crate::common::OrderBy;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -9,16 +11,38 @@ pub struct InstantiateMsg {
     pub prefund_address: String,
     pub kyc_terms_vault_address: String,
     pub ido_token: String,
+    // Price of one whole ido token (10^6 units) in uusd
     pub ido_token_price: Uint128,
     pub end_date: u64,
     pub paused: bool,
     pub minimum_prefund: Uint128,
+    pub staking_gateway_address: String,
+    pub tiers: Vec<Tier>,
+    pub allocation_per_weight: Uint128,
+    // Minimum total commitment for the sale to succeed, zero disables it
+    pub soft_cap: Uint128,
+    // Empty list keeps the sale joinable from instantiation until end_date
+    pub phases: Vec<Phase>,
+    // Maximum total commitment in uusd, zero disables it
+    pub hard_cap: Uint128,
+    // Without vesting the whole ido_token_amount is claimable right after settlement
+    pub vesting: Option<VestingSchedule>,
+    // uusd sent with LeaveIdo and forwarded to the treasury, zero disables it
+    pub leave_penalty: Uint128,
+    pub treasury_address: String,
+    // Router used to convert non-UST prefund contributions into uusd
+    pub terraswap_router: String,
+    // Share of the referee's allocation accrued to the referrer, zero disables it
+    pub referral_share: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    JoinIdo {},
+    JoinIdo {
+        referrer: Option<String>,
+    },
+    LeaveIdo {},
     AcceptOwnership {},
     UpdateConfig {
         owner: Option<String>,
@@ -30,6 +54,54 @@ pub enum ExecuteMsg {
         paused: Option<bool>,
         snapshot_time: Option<u64>,
         minimum_prefund: Option<Uint128>,
+        staking_gateway_address: Option<String>,
+        tiers: Option<Vec<Tier>>,
+        allocation_per_weight: Option<Uint128>,
+        soft_cap: Option<Uint128>,
+        phases: Option<Vec<Phase>>,
+        hard_cap: Option<Uint128>,
+        vesting: Option<VestingSchedule>,
+        leave_penalty: Option<Uint128>,
+        treasury_address: Option<String>,
+        terraswap_router: Option<String>,
+        referral_share: Option<Decimal>,
+    },
+    SettleAllocations {
+        limit: Option<u32>,
+    },
+    ClaimIdoTokens {},
+    CommitLotterySeed {
+        seed_hash: String,
+        winning_tickets: u64,
+    },
+    RevealLotterySeed {
+        seed: String,
+    },
+    DrawLottery {
+        limit: Option<u32>,
+    },
+    CancelIdo {},
+    TakeSnapshot {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // Swaps the sent native coin to uusd and deposits it to the prefund under the IDO contract,
+    // the deposit is credited to the sender and paid for their allocation at settlement
+    Prefund {
+        operations: Vec<SwapOperation>,
+        minimum_receive: Uint128,
+    },
+    Receive(Cw20ReceiveMsg),
+    // Withdraws the unused prefund credit back to the sender, locked while the sender takes part in an unsettled IDO
+    WithdrawPrefundCredit {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    Prefund {
+        operations: Vec<SwapOperation>,
+        minimum_receive: Uint128,
     },
 }
 
@@ -50,6 +122,45 @@ pub enum QueryMsg {
         limit: Option<u32>,
         order_by: Option<OrderBy>,
     },
+    // Same pagination as Participants, with tier, weight and commitment of each participant.
+    // Participants are sorted by canonical address bytes, not by their bech32 strings,
+    // with order_by Asc this is the order the lottery is drawn in
+    ParticipantAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<OrderBy>,
+    },
+    Allocation {
+        address: String,
+    },
+    Lottery {},
+    LotteryResult {
+        address: String,
+    },
+    ClaimableAt {
+        address: String,
+        block_time: Option<u64>,
+    },
+    Snapshot {
+        address: String,
+    },
+    Referrals {
+        address: String,
+    },
+    // Referrers ordered by accrued reward
+    ReferralLeaderboard {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<OrderBy>,
+    },
+    // Evaluates every JoinIdo rule for the address instead of stopping at the first failure
+    Eligibility {
+        address: String,
+        block_time: Option<u64>,
+    },
+    PrefundCredit {
+        address: String,
+    },
 }
 
 // We define a custom struct for each query response
@@ -64,22 +175,96 @@ pub struct ConfigResponse {
     pub paused: bool,
     pub snapshot_time: Option<u64>,
     pub minimum_prefund: Uint128,
+    pub staking_gateway_address: String,
+    pub tiers: Vec<Tier>,
+    pub allocation_per_weight: Uint128,
+    pub soft_cap: Uint128,
+    pub phases: Vec<Phase>,
+    pub hard_cap: Uint128,
+    pub vesting: Option<VestingSchedule>,
+    pub leave_penalty: Uint128,
+    pub treasury_address: String,
+    pub terraswap_router: String,
+    pub referral_share: Decimal,
+}
+
+// tge_unlock share of the tokens is released at tge_time (0.2 is 20%), the rest
+// is released linearly over duration seconds starting after the cliff.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingSchedule {
+    pub tge_time: u64,
+    pub tge_unlock: Decimal,
+    pub cliff: u64,
+    pub duration: u64,
+}
+
+// Users bonding at least min_bond in the staking gateway fall into the tier,
+// which caps their allocation at weight * allocation_per_weight.
+// Allocation in a lottery tier is granted only to the lottery winners.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Tier {
+    pub tier: u32,
+    pub min_bond: Uint128,
+    pub weight: u64,
+    pub lottery: bool,
+}
+
+// Phases are ordered and do not overlap, start_time and end_time are inclusive.
+// minimum_prefund overrides the config one, stakers_only requires a staking gateway bond
+// and max_participants closes the phase once that many users joined in it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Phase {
+    pub name: String,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub minimum_prefund: Option<Uint128>,
+    pub stakers_only: bool,
+    pub max_participants: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct StateResponse {
     pub number_of_participants: u64,
+    pub total_allocation: Uint128,
+    pub raised: Uint128,
+    // remaining and percent_filled are only set when there is a hard cap
+    pub remaining: Option<Uint128>,
+    pub percent_filled: Option<Decimal>,
+    pub is_snapshot_taken: bool,
+    pub is_settled: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ParticipantInfoResponse {
     pub allocation: Uint128,
     pub is_joined: bool,
+    #[serde(default)]
+    pub ido_token_amount: Uint128,
+    #[serde(default)]
+    pub claimed_amount: Uint128,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[derive(Default, Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ParticipantResponse {
     pub is_joined: bool,
+    #[serde(default)]
+    pub tier: Option<u32>,
+    #[serde(default)]
+    pub weight: u64,
+    #[serde(default)]
+    pub lottery: bool,
+    // uusd the participant can be allocated at most, as seen when joining
+    #[serde(default)]
+    pub committed: Uint128,
+    // index of the phase the participant joined in
+    #[serde(default)]
+    pub phase: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ParticipantAccountResponse {
+    pub address: String,
+    pub info: ParticipantResponse,
 }
 
 #[derive(Default, Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -87,6 +272,19 @@ pub struct StatusResponse {
     pub is_closed: bool,
     pub is_paused: bool,
     pub snapshot_time: Option<u64>,
+    // Terminal, set when the owner cancels or the soft cap is missed at end_date
+    pub is_cancelled: bool,
+    pub current_phase: Option<String>,
+    pub time_to_next_phase: Option<u64>,
+}
+
+// Participant data frozen at snapshot_time, settlement reads it instead of live balances
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ParticipantSnapshot {
+    pub available_funds: Uint128,
+    pub is_verified: bool,
+    pub is_accepted: bool,
+    pub bond_amount: Uint128,
 }
 
 // Allocation is in ust token
@@ -94,6 +292,31 @@ pub struct StatusResponse {
 pub struct AllocationInfo {
     pub address: String,
     pub allocation: Uint128,
+    pub ido_token_amount: Uint128,
+    pub claimed_amount: Uint128,
+}
+
+// Winners are drawn over the lottery entrants in the order of ParticipantAccounts with
+// order_by Asc, ascending canonical address bytes, which differs from sorting the bech32
+// addresses. Every entrant wins with probability remaining_tickets / remaining_entrants,
+// using the first 8 bytes of sha256(seed + bech32 address) as a big endian number,
+// so exactly winning_tickets win.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LotteryResponse {
+    pub seed_hash: Option<String>,
+    pub seed: Option<String>,
+    pub entrants: u64,
+    pub winning_tickets: u64,
+    pub winners: u64,
+    pub is_drawn: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LotteryResultResponse {
+    pub address: String,
+    pub is_entrant: bool,
+    pub is_drawn: bool,
+    pub is_winner: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -101,7 +324,76 @@ pub struct ParticipantsResponse {
     pub users: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ParticipantAccountsResponse {
+    pub participants: Vec<ParticipantAccountResponse>,
+}
+
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferralsResponse {
+    pub address: String,
+    pub referrer: Option<String>,
+    pub referees: u64,
+    pub reward: Uint128,
+}
 
-/// We currently take no arguments for migrations
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct ReferrerResponse {
+    pub address: String,
+    pub referees: u64,
+    pub reward: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferralLeaderboardResponse {
+    pub referrers: Vec<ReferrerResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EligibilityRule {
+    NotJoined,
+    NotPaused,
+    NotClosed,
+    NotCancelled,
+    BeforeSnapshot,
+    ActivePhase,
+    PhaseCapacity,
+    Prefund,
+    Kyc,
+    TermsOfUse,
+    Staking,
+    HardCap,
+}
+
+// required and actual carry the numbers the rule compares, if it compares any
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EligibilityCheck {
+    pub rule: EligibilityRule,
+    pub passed: bool,
+    pub required: Option<Uint128>,
+    pub actual: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EligibilityResponse {
+    pub address: String,
+    pub is_eligible: bool,
+    pub checks: Vec<EligibilityCheck>,
+}
+
+// uusd deposited through Prefund that is not paid for an allocation yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PrefundCreditResponse {
+    pub address: String,
+    pub amount: Uint128,
+}
+
+// Addresses the config gained since the first release, only required to migrate an IDO stored in that layout
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    pub staking_gateway_address: Option<String>,
+    pub treasury_address: Option<String>,
+    pub terraswap_router: Option<String>,
+}
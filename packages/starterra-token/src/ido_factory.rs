@@ -0,0 +1,118 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use cosmwasm_std::Uint128;
+use crate::common::OrderBy;
+use crate::ido::{InstantiateMsg as IdoInstantiateMsg, Phase};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub owner: String,
+    pub ido_code_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    UpdateConfig {
+        owner: Option<String>,
+        ido_code_id: Option<u64>,
+    },
+    AcceptOwnership {},
+    StoreTemplate {
+        name: String,
+        template: IdoInstantiateMsg,
+    },
+    RemoveTemplate {
+        name: String,
+    },
+    // Instantiates a new IDO from the template, overriding the sale specific fields
+    CreateIdo(CreateIdoMsg),
+    // Re-reads the status of a registered IDO and moves it in the status index, callable by anyone
+    SyncIdoStatus {
+        ido_id: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreateIdoMsg {
+    pub template: String,
+    pub label: String,
+    pub ido_token: String,
+    pub ido_token_price: Uint128,
+    pub end_date: u64,
+    pub phases: Option<Vec<Phase>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    Template {
+        name: String,
+    },
+    Ido {
+        ido_id: u64,
+    },
+    Idos {
+        status: Option<IdoStatus>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        order_by: Option<OrderBy>,
+    },
+    // Scans at most limit IDOs after start_after, in ascending id order
+    IsParticipantInAnyIdo {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IdoStatus {
+    Upcoming,
+    Live,
+    Closed,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: String,
+    pub ido_code_id: u64,
+    pub number_of_idos: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TemplateResponse {
+    pub name: String,
+    pub template: IdoInstantiateMsg,
+}
+
+// Status is the one recorded at registration or the last SyncIdoStatus, cancelled sales are reported as closed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IdoResponse {
+    pub ido_id: u64,
+    pub address: String,
+    pub label: String,
+    pub template: String,
+    pub created_at: u64,
+    pub status: IdoStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IdosResponse {
+    pub idos: Vec<IdoResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsParticipantResponse {
+    pub address: String,
+    pub is_participant: bool,
+    // Last IDO id scanned, pass it as start_after to continue when the address was not found
+    pub last_ido_id: Option<u64>,
+}
+
+/// We currently take no arguments for migrations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
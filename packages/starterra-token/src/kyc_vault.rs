@@ -1,26 +1,77 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use cosmwasm_std::Binary;
+use crate::common::OrderBy;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: String,
-    pub kyc_provider_address: String,
+    pub kyc_providers: Vec<String>,
+    // Seconds a verification stays valid when no expiry is given, zero never expires
+    pub default_validity: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    AcceptTermsOfUse {},
+    // version 0 stands for the terms in place before versioning
+    AcceptTermsOfUse {
+        version: u32,
+    },
+    PublishTermsOfUse {
+        content_hash: String,
+        effective_from: u64,
+    },
     UpdateConfig {
         owner: Option<String>,
-        kyc_provider_address: Option<String>,
+        default_validity: Option<u64>,
     },
     AcceptOwnership {},
+    // owner only, moves up to limit entries stored before records existed, repeat until none are migrated
+    MigrateLegacyEntries {
+        limit: Option<u32>,
+    },
+    // pubkey is the secp256k1 key the provider signs attestations with
+    AddProvider {
+        address: String,
+        pubkey: Option<Binary>,
+    },
+    // invalidate_verifications revokes everything the provider registered so far
+    RemoveProvider {
+        address: String,
+        invalidate_verifications: bool,
+    },
+    // moves KYC and terms of use state to a new wallet of the same user, each wallet takes part in one link per side
+    LinkAddress {
+        old: String,
+        new: String,
+    },
+    // the blocklist is managed by the owner and the providers, a block is lifted by the owner or whoever added it
+    BlockAddresses {
+        addresses: Vec<String>,
+    },
+    UnblockAddresses {
+        addresses: Vec<String>,
+    },
+    // signature of sha256("{contract}:{sender}:{level}:{expiry}") by a registered provider key
+    SubmitAttestation {
+        expiry: u64,
+        level: KycLevel,
+        signature: Binary,
+    },
     RegisterAddress {
         address: String,
+        expires_at: Option<u64>,
+        level: Option<KycLevel>,
+        country: Option<String>,
+        reference_hashes: Option<Vec<String>>,
     },
+    // level and country are shared by the whole batch
     RegisterAddresses {
         addresses: Vec<String>,
+        expires_at: Option<u64>,
+        level: Option<KycLevel>,
+        country: Option<String>,
     },
     UnregisterAddress {
         address: String,
@@ -39,36 +90,195 @@ pub enum QueryMsg {
     IsAccepted {
         address: String,
     },
+    // defaults to the version currently in effect
+    TermsOfUse {
+        version: Option<u32>,
+    },
     IsAcceptedVerified {
         address: String,
     },
+    KycRecord {
+        address: String,
+    },
+    // addresses without a country never pass a non-empty blocked set
+    IsVerifiedAtLevel {
+        address: String,
+        min_level: KycLevel,
+        blocked_countries: Vec<String>,
+    },
+    Providers {},
+    VerifiedAddresses {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<OrderBy>,
+    },
+    AcceptedAddresses {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<OrderBy>,
+    },
+    BlockedAddresses {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<OrderBy>,
+    },
+    AddressLinks {
+        address: String,
+    },
+    Stats {},
     Config {},
 }
 
+// Ordered from the least to the most strict due diligence
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KycLevel {
+    Basic,
+    Enhanced,
+    Accredited,
+}
+
+impl Default for KycLevel {
+    fn default() -> Self {
+        KycLevel::Basic
+    }
+}
+
+impl KycLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KycLevel::Basic => "basic",
+            KycLevel::Enhanced => "enhanced",
+            KycLevel::Accredited => "accredited",
+        }
+    }
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
     pub owner: String,
-    pub kyc_provider_address: String,
+    pub default_validity: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProviderResponse {
+    pub address: String,
+    pub added_at: u64,
+    pub pubkey: Option<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProvidersResponse {
+    pub providers: Vec<ProviderResponse>,
+}
+
+// expires_at is reported for expired verifications too
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct IsVerifiedResponse {
     pub address: String,
     pub is_verified: bool,
+    pub expires_at: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct IsAcceptedResponse {
     pub address: String,
     pub is_accepted: bool,
+    pub accepted_version: Option<u32>,
+    pub current_version: u32,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TermsOfUseResponse {
+    pub version: u32,
+    pub content_hash: String,
+    pub effective_from: u64,
+}
+
+// blocked addresses are reported as neither accepted nor verified
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct IsAcceptedVerifiedResponse {
     pub address: String,
     pub is_accepted: bool,
     pub is_verified: bool,
+    pub expires_at: Option<u64>,
+    pub is_blocked: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BlockedAddressResponse {
+    pub address: String,
+    pub blocked_at: u64,
+    pub blocked_by: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BlockedAddressesResponse {
+    pub addresses: Vec<BlockedAddressResponse>,
+}
+
+// country is an ISO 3166-1 alpha-2 code, reference_hashes are sha256 hex digests of provider references
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct KycRecordResponse {
+    pub address: String,
+    pub is_verified: bool,
+    pub provider: Option<String>,
+    pub level: Option<KycLevel>,
+    pub country: Option<String>,
+    pub reference_hashes: Vec<String>,
+    pub verified_at: Option<u64>,
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsVerifiedAtLevelResponse {
+    pub address: String,
+    pub is_verified: bool,
+    pub level: Option<KycLevel>,
+    pub country: Option<String>,
+}
+
+// expired and invalidated records are listed too, check is_verified
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VerifiedAddressesResponse {
+    pub records: Vec<KycRecordResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AcceptedAddressResponse {
+    pub address: String,
+    pub accepted_version: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AcceptedAddressesResponse {
+    pub addresses: Vec<AcceptedAddressResponse>,
+}
+
+// totals of records written: expired, invalidated and blocked verifications and acceptances
+// of any version are still counted, entries stored before records existed once migrated
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatsResponse {
+    pub total_verified: u64,
+    pub total_accepted: u64,
+    pub total_verified_accepted: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AddressLinkResponse {
+    pub old_address: String,
+    pub new_address: String,
+    pub linked_at: u64,
+    pub linked_by: String,
+}
+
+// linked_from is the wallet the address took over, linked_to the one it moved to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AddressLinksResponse {
+    pub address: String,
+    pub linked_from: Option<AddressLinkResponse>,
+    pub linked_to: Option<AddressLinkResponse>,
 }
 
 /// We currently take no arguments for migrations
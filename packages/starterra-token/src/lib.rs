@@ -2,6 +2,7 @@ pub mod airdrop_genesis;
 pub mod common;
 pub mod ido_prefund;
 pub mod ido;
+pub mod ido_factory;
 pub mod kyc_vault;
 pub mod staking;
 pub mod staking_gateway;
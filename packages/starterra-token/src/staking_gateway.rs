@@ -1,11 +1,35 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Decimal, Uint128};
+use crate::common::OrderBy;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: String,
-    pub staking_contracts: Vec<String>,
+    pub staking_contracts: Vec<StakingContractInfo>,
+    pub multi_faction: bool,
+    pub faction_weights: Vec<FactionWeight>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StakingType {
+    Lp,
+    Stt,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakingContractInfo {
+    pub address: String,
+    pub faction: String,
+    pub staking_type: StakingType,
+}
+
+// Contracts without a weight count with a weight of one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FactionWeight {
+    pub staking_contract: String,
+    pub weight: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -13,14 +37,45 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     UpdateConfig {
         owner: Option<String>,
-        staking_contracts: Option<Vec<String>>,
+        multi_faction: Option<bool>,
+        faction_weights: Option<Vec<FactionWeight>>,
     },
     AcceptOwnership {},
+    AddStakingContract {
+        address: String,
+        faction: String,
+        staking_type: StakingType,
+    },
+    // inactive staking contracts are skipped by the stake queries
+    UpdateStakingContract {
+        address: String,
+        faction: Option<String>,
+        staking_type: Option<StakingType>,
+        active: Option<bool>,
+    },
+    RemoveStakingContract {
+        address: String,
+    },
+    // sent by a registered staking contract after every change of a bond, with the resulting bond
+    // of the staker and the resulting total bond of the contract, checkpointed at the block time
+    UpdateBondCheckpoint {
+        staker: String,
+        bond_amount: Uint128,
+        total_bond_amount: Uint128,
+    },
+}
+
+// The staking type of every staking contract kept in the legacy config, their faction is read from the contracts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    pub staking_types: Vec<LegacyStakingContract>,
 }
 
-/// We currently take no arguments for migrations
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct LegacyStakingContract {
+    pub address: String,
+    pub staking_type: StakingType,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -29,17 +84,37 @@ pub enum QueryMsg {
     CanUserStake {
         user: String,
     },
+    // in multi faction mode bond_amount is the total weighted stake
     BondAmount {
         user: String,
     },
+    BondAmounts {
+        user: String,
+    },
+    // bond amounts of the latest checkpoints at or before the given time, for the active staking contracts,
+    // contracts without a checkpoint by then count as zero
+    BondAmountAt {
+        user: String,
+        block_time: u64,
+    },
+    TotalStakedAt {
+        block_time: u64,
+    },
+    // active staking contracts
     Addresses {},
+    StakingContracts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<OrderBy>,
+    },
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
     pub owner: String,
-    pub staking_contracts: Vec<String>,
+    pub multi_faction: bool,
+    pub faction_weights: Vec<FactionWeight>,
 }
 
 // We define a custom struct for each query response
@@ -63,8 +138,48 @@ pub struct BondAmountResponse {
     pub bond_amount: Uint128,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractBondResponse {
+    pub staking_contract: String,
+    pub bond_amount: Uint128,
+    pub weight: Decimal,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BondAmountsResponse {
+    pub user: String,
+    pub bonds: Vec<ContractBondResponse>,
+    pub total_bond_amount: Uint128,
+    pub total_weighted_bond_amount: Uint128,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct AddressesResponse {
     pub addresses: Vec<String>,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakingContractResponse {
+    pub address: String,
+    pub faction: String,
+    pub staking_type: StakingType,
+    pub active: bool,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakingContractsResponse {
+    pub staking_contracts: Vec<StakingContractResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalStakedResponse {
+    pub block_time: u64,
+    pub total_bond_amount: Uint128,
+    pub total_weighted_bond_amount: Uint128,
+}